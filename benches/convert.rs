@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Measure the throughput of the conversion of a large document to HTML.
+//! The document is generated, so that the results only depend on the code being measured.
+
+extern crate asciidoctor;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 20;
+const SECTIONS: usize = 200;
+
+/// Generate a document with the most common blocks and inline formatting.
+fn document() -> String {
+    let mut document = "= Benchmark\nAuthor Name <author@example.com>\n:toc:\n\n".to_string();
+    for index in 0..SECTIONS {
+        document.push_str(&format!("[#section-{0}.chapter]\n== Section {0}\n\n", index));
+        document.push_str("Some *bold*, _italic_ and `code` text with a link:https://example.org[link] and a \
+            [.role#span-id]#span#.\nA second line with <<section-0,a cross reference>> and ^super^ and ~sub~.\n\n");
+        document.push_str("* First item\n** Nested item with *bold*\n* Second item\n\n");
+        document.push_str("[cols=\"1,2*\"]\n|===\n|A |B |C\n|D |E |F\n|===\n\n");
+        document.push_str("[source,rust]\n----\nfn main() {\n    println!(\"Hello\");\n}\n----\n\n");
+        document.push_str("NOTE: An admonition paragraph.\n\n");
+    }
+    document
+}
+
+fn main() {
+    let document = document();
+    let attributes = HashMap::new();
+    let mut output = Vec::with_capacity(document.len() * 4);
+    let mut times = vec![];
+    for _ in 0..ITERATIONS {
+        output.clear();
+        let start = Instant::now();
+        asciidoctor::convert(document.as_bytes(), &mut output, &attributes).expect("cannot convert the document");
+        times.push(start.elapsed());
+    }
+    let total: Duration = times.iter().sum();
+    let mean = total / ITERATIONS as u32;
+    let min = times.iter().min().cloned().unwrap_or_default();
+    let megabytes = document.len() as f64 / 1_000_000.0;
+    println!("convert: {} bytes in, {} bytes out", document.len(), output.len());
+    println!("convert: mean {:?}, min {:?}, {:.2} MB/s", mean, min, megabytes / mean.as_secs_f64());
+}
@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Document attributes set by attribute entries like `:name: value`.
+
+use std::collections::{HashMap, HashSet};
+
+/// The intrinsic attributes, which are defined in every document, with their HTML values.
+const INTRINSIC_ATTRIBUTES: &[(&str, &str)] = &[
+    ("amp", "&amp;"),
+    ("apos", "&#39;"),
+    ("backslash", "\\"),
+    ("backtick", "`"),
+    ("brvbar", "&#166;"),
+    ("caret", "^"),
+    ("cpp", "C&#43;&#43;"),
+    ("deg", "&#176;"),
+    ("empty", ""),
+    ("endsb", "]"),
+    ("gt", "&gt;"),
+    ("ldquo", "&#8220;"),
+    ("lsquo", "&#8216;"),
+    ("lt", "&lt;"),
+    ("nbsp", "&#160;"),
+    ("plus", "&#43;"),
+    ("quot", "&#34;"),
+    ("rdquo", "&#8221;"),
+    ("rsquo", "&#8217;"),
+    ("sp", " "),
+    ("startsb", "["),
+    ("tilde", "~"),
+    ("two-colons", "::"),
+    ("two-semicolons", ";;"),
+    ("vbar", "|"),
+    ("wj", "&#8288;"),
+    ("zwsp", "&#8203;"),
+];
+
+/// The attributes set by default in every document, which the document can unset, like the labels
+/// of the captions.
+const DEFAULT_ATTRIBUTES: &[(&str, &str)] = &[
+    ("equation-caption", "Equation"),
+    ("example-caption", "Example"),
+    ("figure-caption", "Figure"),
+    ("table-caption", "Table"),
+];
+
+/// The document attributes in effect at some point of a document.
+#[derive(Clone, Debug)]
+pub struct AttributeRegistry {
+    attributes: HashMap<String, String>,
+    /// The attributes which the attribute entries of the document cannot change.
+    locked: HashSet<String>,
+}
+
+impl AttributeRegistry {
+    /// Create a registry with the intrinsic attributes, like `nbsp`, and the default attributes, like
+    /// `figure-caption`.
+    pub fn new() -> Self {
+        let mut attributes = HashMap::new();
+        for &(name, value) in INTRINSIC_ATTRIBUTES.iter().chain(DEFAULT_ATTRIBUTES) {
+            attributes.insert(name.to_string(), value.to_string());
+        }
+        AttributeRegistry {
+            attributes,
+            locked: HashSet::new(),
+        }
+    }
+
+    /// Create a registry with the intrinsic attributes and the `initial` attributes, like the ones
+    /// given with `-a name=value` to asciidoctor.
+    /// An initial attribute whose name, like `sectnums@`, or value ends with `@` is locked: the `@` is
+    /// removed and the attribute entries of the document cannot change it.
+    pub fn with_attributes(initial: &HashMap<String, String>) -> Self {
+        let mut registry = AttributeRegistry::new();
+        for (name, value) in initial {
+            match (name.strip_suffix('@'), value.strip_suffix('@')) {
+                (None, None) => registry.set(name, value),
+                (locked_name, locked_value) => {
+                    let name = locked_name.unwrap_or(name);
+                    registry.set(name, locked_value.unwrap_or(value));
+                    registry.locked.insert(name.to_string());
+                },
+            }
+        }
+        registry
+    }
+
+    /// Get the value of the attribute `name`, if it is set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Check whether the attribute `name` is set, whatever its value.
+    pub fn is_set(&self, name: &str) -> bool {
+        self.attributes.contains_key(name)
+    }
+
+    /// Check whether the attribute `name` has its intrinsic value, which is HTML, like `&#160;` for
+    /// `nbsp`.
+    pub fn is_intrinsic(&self, name: &str) -> bool {
+        INTRINSIC_ATTRIBUTES.iter().any(|&(intrinsic, value)| intrinsic == name && self.get(name) == Some(value))
+    }
+
+    /// Check whether the attribute `name` is locked by the initial attributes.
+    pub fn is_locked(&self, name: &str) -> bool {
+        self.locked.contains(name)
+    }
+
+    /// Set the attribute `name` to `value`, unless it is locked.
+    pub fn set(&mut self, name: &str, value: &str) {
+        if self.is_locked(name) {
+            return;
+        }
+        self.attributes.insert(name.to_string(), value.to_string());
+    }
+
+    /// Unset the attribute `name`, unless it is locked.
+    pub fn unset(&mut self, name: &str) {
+        if self.is_locked(name) {
+            return;
+        }
+        self.attributes.remove(name);
+    }
+}
+
+impl Default for AttributeRegistry {
+    fn default() -> Self {
+        AttributeRegistry::new()
+    }
+}
@@ -0,0 +1,51 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! The counters of the captioned blocks of a document, like `Figure 2.` or `Table 1.`.
+
+use std::collections::HashMap;
+
+/// The numbers of the last captioned blocks of each kind, like the figures and the tables, which
+/// are counted independently.
+#[derive(Debug, Default)]
+pub struct CaptionRegistry {
+    counters: HashMap<String, usize>,
+}
+
+impl CaptionRegistry {
+    /// Create a registry where no block is counted yet.
+    pub fn new() -> Self {
+        CaptionRegistry::default()
+    }
+
+    /// Count the next captioned block of the `kind`, like `figure`, and get its caption made of the
+    /// `label` and its number, like `Figure 2. `.
+    pub fn caption(&mut self, kind: &str, label: &str) -> String {
+        let number = self.counters.entry(kind.to_string()).or_insert(0);
+        *number += 1;
+        format!("{} {}. ", label, number)
+    }
+
+    /// Get the number of the last captioned block of the `kind`, or 0 when there is none.
+    pub fn number(&self, kind: &str) -> usize {
+        self.counters.get(kind).cloned().unwrap_or(0)
+    }
+}
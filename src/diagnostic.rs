@@ -0,0 +1,83 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Diagnostics reported while parsing a document, which do not stop the parsing.
+
+use position::Pos;
+
+/// A diagnostic, with the position in the document where the problem was found.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub pos: Pos,
+}
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Warning,
+}
+
+impl Level {
+    /// Get the name of the level, like `warning`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Warning => "warning",
+        }
+    }
+}
+
+/// Serialize the `diagnostics` to a JSON array, for the tools reading them, like a linter: each
+/// one is an object with its `level`, `message`, `line` and `column`.
+/// The JSON is written by hand rather than behind a `serde` feature, so that the crate keeps having
+/// no dependency for such a small format.
+pub fn warnings_as_json(diagnostics: &[Diagnostic]) -> String {
+    let mut json = "[".to_string();
+    for (index, diagnostic) in diagnostics.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(r#"{{"level":"{}","message":"#, diagnostic.level.name()));
+        push_json_string(&mut json, &diagnostic.message);
+        json.push_str(&format!(r#","line":{},"column":{}}}"#, diagnostic.pos.line, diagnostic.pos.column));
+    }
+    json.push(']');
+    json
+}
+
+/// Push `string` to the `json` as a JSON string, with its quotes, backslashes and control
+/// characters escaped.
+fn push_json_string(json: &mut String, string: &str) {
+    json.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char < ' ' => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
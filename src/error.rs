@@ -20,24 +20,38 @@
  */
 
 use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
 use std::io;
 use std::result;
-use std::string::FromUtf8Error;
+use std::string::{FromUtf8Error, String, ToString};
+use std::vec::Vec;
 
 use position::Pos;
-use self::Error::{Eof, Msg, UnexpectedChar, UnexpectedToken};
+use self::Error::{Eof, InvalidUtf8, MaxDepthExceeded, Msg, Strict, UnexpectedChar, UnexpectedEof,
+    UnexpectedToken};
 
 pub type Result<T> = result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     Eof,
+    /// The document is not valid UTF-8 from the given position.
+    InvalidUtf8(Pos),
+    /// The blocks or the includes are nested deeper than the maximum depth, which is given.
+    MaxDepthExceeded(usize),
     Msg(String),
+    /// A warning found at the given position, which is an error because the parser is strict.
+    Strict {
+        message: String,
+        pos: Pos,
+    },
     UnexpectedChar {
         actual: u8,
         expected: Vec<u8>,
         pos: Pos,
     },
+    /// The document ends in the block starting at the given position, which is not terminated.
+    UnexpectedEof(Pos),
     UnexpectedToken {
         actual: String,
         expected: String,
@@ -49,10 +63,14 @@ impl Display for Error {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         match *self {
             Eof => write!(fmt, "end of file"),
+            InvalidUtf8(ref pos) => write!(fmt, "{}:{}: invalid UTF-8", pos.line, pos.column),
+            MaxDepthExceeded(max_depth) => write!(fmt, "maximum nesting depth of {} exceeded", max_depth),
             Msg(ref message) => write!(fmt, "{}", message),
+            Strict { ref message, ref pos } => write!(fmt, "{}:{}: {}", pos.line, pos.column, message),
             UnexpectedChar { ref actual, ref expected, ref pos } =>
                 write!(fmt, "{}:{}: expected {}, but found `{}` on line {}, column {}", pos.line, pos.column,
                        expected_chars(expected), actual, pos.line, pos.column),
+            UnexpectedEof(ref pos) => write!(fmt, "{}:{}: unexpected end of file in this block", pos.line, pos.column),
             UnexpectedToken { ref actual, ref expected, ref pos } =>
                 write!(fmt, "{}:{}: expected {}, but found `{}` on line {}, column {}", pos.line, pos.column, expected,
                        actual, pos.line, pos.column),
@@ -60,12 +78,13 @@ impl Display for Error {
     }
 }
 
-impl<'a> From<&'a str> for Error {
+impl From<&str> for Error {
     fn from(string: &str) -> Self {
         Msg(string.to_string())
     }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
         Msg(error.to_string())
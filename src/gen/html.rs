@@ -21,22 +21,35 @@
 
 //! Generate HTML from the asciidoctor nodes.
 
-use std::io::Write;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, Write};
 
-use error::Result;
+use attributes::AttributeRegistry;
+use captions::CaptionRegistry;
+use error::{Error, Result};
+use gen::Backend;
+use ids::IdStyle;
+use parser::{self, Parser};
 use node::{Attribute, Node};
-use node::Attribute::Role;
+use node::Attribute::{Role, Style};
 use node::Node::*;
-use node::{Item, Tag, Text};
+use node::{self, AdmonitionKind, Author, Block, Cell, CellContent, CellStyle, ColumnSpec, DescriptionItem, Header, Item,
+    ListItem, MediaKind, Section, StemKind, Tag, Text};
+use node::Tag::{Bold, InlineCode, Italic};
 use self::Html::*;
+use visitor::{Visitor, walk_node};
 
 macro_rules! attr {
     ($( $name:ident = $value:expr ),*) => {{
         let mut attributes = String::new();
         $(
+            if !attributes.is_empty() {
+                attributes.push(' ');
+            }
             attributes.push_str(stringify!($name));
             attributes.push_str("=\"");
-            attributes.push_str(&$value.to_string());
+            attributes.push_str(&escape_attribute(&$value.to_string()));
             attributes.push_str("\"");
         )*
         attributes
@@ -45,45 +58,676 @@ macro_rules! attr {
 
 type Id = String;
 
+/// The HTML elements which have no children nor closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Get the default stylesheet, compatible with the classes of the generated HTML, to embed it in a
+/// standalone document.
+pub fn default_stylesheet() -> &'static str {
+    include_str!("default.css")
+}
+
+/// Write the resulting HTML code for the whole document made of `nodes` in the `writer`.
+/// Unlike `gen`, this can generate the table of contents.
+pub fn gen_document<G: HtmlGen, W: Write>(gen: &mut G, nodes: &[Node], writer: &mut W) -> Result<()> {
+    gen_document_with_transform(gen, nodes, writer, |html| html)
+}
+
+/// Like `gen_document`, but write the HTML returned by `transform` for the generated one, to
+/// post-process it, like to rewrite the links.
+pub fn gen_document_with_transform<G, W, F>(gen: &mut G, nodes: &[Node], writer: &mut W, transform: F) -> Result<()>
+    where G: HtmlGen,
+          W: Write,
+          F: FnOnce(Html) -> Html,
+{
+    let html = gen.document(nodes);
+    let xhtml = xhtml(gen);
+    transform(html).write_with_syntax(writer, xhtml)
+}
+
+/// Write the HTML code of the inline `content`, like a comment, in the `writer`: only the inline
+/// substitutions apply, like the formatting and the macros, without the paragraph wrapping it.
+pub fn gen_inline<G: HtmlGen, W: Write>(gen: &mut G, content: &str, writer: &mut W) -> Result<()> {
+    let attributes = gen.attributes().clone();
+    let text = parser::inline_text(content, &attributes)?;
+    let html = gen.text(&text);
+    html.write_with_syntax(writer, xhtml(gen))
+}
+
+/// Write a standalone HTML page for the whole document made of `nodes` in the `writer`, with the
+/// default stylesheet embedded in it and the class of the body taken from the `doctype` attribute.
+pub fn gen_standalone<G: HtmlGen, W: Write>(gen: &mut G, nodes: &[Node], writer: &mut W) -> Result<()> {
+    let title =
+        match nodes.first() {
+            Some(Header(header)) => escape(&header.title.plain()),
+            _ => "Untitled".to_string(),
+        };
+    let body = gen.standalone(nodes);
+    let mut body_class = escape_attribute(gen.attributes().get("doctype").unwrap_or("article"));
+    if let Some(side) = toc_sidebar(gen.attributes()) {
+        body_class.push_str(&format!(" toc2 toc-{}", side));
+    }
+    write!(writer, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<style>\n{}</style>\n\
+                    </head>\n<body class=\"{}\">\n", title, default_stylesheet(), body_class)?;
+    body.write_with_syntax(writer, xhtml(gen))?;
+    write!(writer, "\n</body>\n</html>\n")?;
+    Ok(())
+}
+
+/// Write the HTML code of each top-level node of the `parser` in the `writer` as soon as it is
+/// parsed, and flush it, instead of generating the whole document first.
+/// Since the blocks following a node are not known yet when it is written, there is no table of
+/// contents and the cross references only use the text of the blocks preceding them.
+pub fn gen_streaming<G, R, W>(gen: &mut G, parser: &mut Parser<R>, writer: &mut W) -> Result<()>
+    where G: HtmlGen,
+          R: BufRead,
+          W: Write,
+{
+    loop {
+        let node =
+            match parser.node() {
+                Ok(node) => node,
+                Err(Error::Eof) => break,
+                Err(error) => return Err(error),
+            };
+        let html = gen.node(&node);
+        html.write_with_syntax(writer, xhtml(gen))?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
 /// Write the resulting HTML code for the specified `node` in the `writer`.
 pub fn gen<G: HtmlGen, W: Write>(gen: &mut G, node: &Node, writer: &mut W) -> Result<()> {
+    gen_with_transform(gen, node, writer, |html| html)
+}
+
+/// Like `gen`, but write the HTML returned by `transform` for the generated one.
+pub fn gen_with_transform<G, W, F>(gen: &mut G, node: &Node, writer: &mut W, transform: F) -> Result<()>
+    where G: HtmlGen,
+          W: Write,
+          F: FnOnce(Html) -> Html,
+{
     let html = gen.node(node);
-    html.write(writer)
+    let xhtml = xhtml(gen);
+    transform(html).write_with_syntax(writer, xhtml)
 }
 
 /// The default HTML generator.
+#[derive(Default)]
 pub struct Generator {
+    attributes: AttributeRegistry,
+    captions: CaptionRegistry,
+    id_style: IdStyle,
+    references: HashMap<String, String>,
+    section_numbers: Vec<usize>,
+    toc_macro: Option<Html>,
+}
+
+impl Generator {
+    /// Create a new generator.
+    pub fn new() -> Self {
+        Generator::default()
+    }
+
+    /// Create a new generator starting with the document `attributes`.
+    pub fn with_attributes(attributes: AttributeRegistry) -> Self {
+        Generator {
+            attributes,
+            captions: CaptionRegistry::new(),
+            id_style: IdStyle::default(),
+            references: HashMap::new(),
+            section_numbers: vec![],
+            toc_macro: None,
+        }
+    }
+
+    /// Generate the IDs of the sections without an explicit ID in the `style`, like
+    /// `IdStyle::GitHub`, when the document is converted with this generator.
+    pub fn id_style(mut self, style: IdStyle) -> Self {
+        self.id_style = style;
+        self
+    }
 }
 
 /// Genarate an HTML node from a asciidoctor node.
 pub trait HtmlGen {
+    /// The document attributes in effect at the current point of the generation.
+    fn attributes(&mut self) -> &mut AttributeRegistry;
+
+    /// The numbers of the captioned blocks generated so far, like the figures and the tables.
+    fn captions(&mut self) -> &mut CaptionRegistry;
+
+    /// The style of the IDs generated for the sections of the converted documents.
+    fn id_style(&self) -> IdStyle {
+        IdStyle::default()
+    }
+
+    /// The text of the cross references to the IDs of the blocks, like their `reftext`.
+    fn references(&mut self) -> &mut HashMap<String, String>;
+
+    /// The numbers of the last sections generated at each level, for `:sectnums:`.
+    fn section_numbers(&mut self) -> &mut Vec<usize>;
+
+    /// The table of contents of the document, generated with its header when `:toc: macro` is set,
+    /// to be placed where the `toc::[]` macro is.
+    fn toc_macro(&mut self) -> &mut Option<Html>;
+
+    /// Generate an admonition block, as a table with the icon of its kind next to its blocks: a font
+    /// icon with `:icons: font`, or the title of its kind otherwise, like `Note`. It has the `alert`
+    /// ARIA role for the cautions and the warnings, and the `note` role for the others, when
+    /// `:aria-roles:` is set.
+    fn admonition(&mut self, kind: AdmonitionKind, blocks: &[Block], attributes: &[Attribute]) -> Html {
+        let icon =
+            if self.has_font_icons() {
+                i_a(attr! { class = format!("fa icon-{}", kind.name()), title = kind.title() }, Empty)
+            }
+            else {
+                div_a(attr! { class = "title" }, SingleTextNode(kind.title().to_string()))
+            };
+        let icon = td_a(attr! { class = "icon" }, icon);
+        let content = Seq(Box::new(self.block_title(attributes)), Box::new(self.blocks(blocks)));
+        let content = td_a(attr! { class = "content" }, content);
+        let mut div_attributes = block_attributes(&format!("admonitionblock {}", kind.name()), attributes);
+        if self.attributes().is_set("aria-roles") {
+            let role =
+                match kind {
+                    AdmonitionKind::Caution | AdmonitionKind::Warning => "alert",
+                    AdmonitionKind::Important | AdmonitionKind::Note | AdmonitionKind::Tip => "note",
+                };
+            div_attributes.push(' ');
+            div_attributes.push_str(&attr! { role = role });
+        }
+        div_a(div_attributes, table_a(String::new(), tr(Seq(Box::new(icon), Box::new(content)))))
+    }
+
+    fn attribute_entry(&mut self, name: &str, value: &Option<String>) -> Html {
+        match *value {
+            Some(ref value) => self.attributes().set(name, value),
+            None => self.attributes().unset(name),
+        }
+        Empty
+    }
+
+    /// Generate the details of the author at the 1-based `position` of the author line.
+    fn author(&mut self, author: &Author, position: usize) -> Html {
+        let suffix =
+            if position == 1 {
+                String::new()
+            }
+            else {
+                position.to_string()
+            };
+        let fullname = SingleTextNode(escape(&author.fullname()));
+        let mut details = vec![
+            span_a(attr! { id = format!("author{}", suffix), class = "author" }, fullname),
+            Br,
+        ];
+        if let Some(ref email) = author.email {
+            let link = a_a(attr! { href = format!("mailto:{}", email) }, SingleTextNode(escape(email)));
+            details.push(span_a(attr! { id = format!("email{}", suffix), class = "email" }, link));
+            details.push(Br);
+        }
+        TextNode(details)
+    }
+
+    fn button(&mut self, label: &str) -> Html {
+        b_a(attr! { class = "button" }, SingleTextNode(label.to_string()))
+    }
+
+    /// Generate the title of a block, from its `title` attribute, like `.Title` on the line
+    /// preceding it.
+    fn block_title(&mut self, attributes: &[Attribute]) -> Html {
+        match named(attributes, "title") {
+            Some(title) => div_a(attr! { class = "title" }, self.inline(title)),
+            None => Empty,
+        }
+    }
+
+    /// Generate a sequence of blocks.
+    fn blocks(&mut self, blocks: &[Block]) -> Html {
+        let mut children = vec![];
+        for block in blocks {
+            children.push(self.node(&block.node));
+        }
+        TextNode(children)
+    }
+
+    /// Get the caption of the next titled block of the `kind`, like `Figure 2. ` for `figure`: the
+    /// label in the `{kind}-caption` attribute followed by the number of the block.
+    /// There is no caption when the attribute is unset, like `listing-caption` by default.
+    /// The cross references to the block with the `attributes` then default to its label and number,
+    /// like `Table 1`.
+    fn caption(&mut self, kind: &str, attributes: &[Attribute]) -> Option<String> {
+        let label = self.attributes().get(&format!("{}-caption", kind))?.to_string();
+        let caption = self.captions().caption(kind, &label);
+        let number = self.captions().number(kind);
+        if let Some((id, text)) = caption_reference(attributes, &label, number) {
+            self.references().insert(id, text);
+        }
+        Some(caption)
+    }
+
+    /// Generate the title of a block of the `kind`, prefixed with its caption, like
+    /// `Example 1. Title`.
+    fn captioned_title(&mut self, kind: &str, attributes: &[Attribute]) -> Html {
+        match named(attributes, "title") {
+            Some(title) => div_a(attr! { class = "title" }, self.captioned_text(kind, title, attributes)),
+            None => Empty,
+        }
+    }
+
+    /// Generate the `title` of a block of the `kind` with the `attributes`, prefixed with its
+    /// caption, if any.
+    fn captioned_text(&mut self, kind: &str, title: &str, attributes: &[Attribute]) -> Html {
+        let title = self.inline(title);
+        match self.caption(kind, attributes) {
+            Some(caption) => Seq(Box::new(SingleTextNode(caption)), Box::new(title)),
+            None => title,
+        }
+    }
+
+    /// Generate a description list, as a table with the `horizontal` style.
+    fn description_list(&mut self, items: &[DescriptionItem], attributes: &[Attribute]) -> Html {
+        let horizontal = style(attributes) == Some("horizontal");
+        let mut children = vec![];
+        for item in items {
+            let term = self.text(&item.term);
+            let mut description = vec![];
+            if !item.description.items.is_empty() {
+                description.push(p(self.text(&item.description)));
+            }
+            for block in &item.blocks {
+                description.push(self.node(&block.node));
+            }
+            if horizontal {
+                children.push(tr(Seq(
+                    Box::new(td_a(attr! { class = "hdlist1" }, term)),
+                    Box::new(td_a(attr! { class = "hdlist2" }, TextNode(description))),
+                )));
+            }
+            else {
+                children.push(dt_a(attr! { class = "hdlist1" }, term));
+                if !description.is_empty() {
+                    children.push(dd(TextNode(description)));
+                }
+            }
+        }
+        if horizontal {
+            div_a(
+                attr! { class = classes("hdlist", attributes) },
+                table_a(String::new(), TextNode(children)),
+            )
+        }
+        else {
+            div_a(
+                attr! { class = classes("dlist", attributes) },
+                dl(TextNode(children)),
+            )
+        }
+    }
+
+    /// Generate a whole document, with its table of contents when `:toc:` is set.
+    /// The attribute entries following the header are applied before generating it, so that they
+    /// can enable the table of contents.
+    fn document(&mut self, nodes: &[Node]) -> Html {
+        let (header, rest) = self.document_header(nodes);
+        let mut children = vec![header];
+        for node in rest {
+            children.push(self.node(node));
+        }
+        TextNode(children)
+    }
+
+    /// Generate the header of a whole document, with its table of contents, after applying the
+    /// attribute entries following it.
+    /// Return it with the nodes following it.
+    /// With `:toc: macro`, the table of contents is kept for the `toc::[]` macro instead.
+    fn document_header<'a>(&mut self, nodes: &'a [Node]) -> (Html, &'a [Node]) {
+        let mut references = References::new(self.attributes().clone());
+        references.visit_nodes(nodes);
+        self.references().extend(references.references);
+        let mut rest = nodes;
+        let header =
+            match nodes.split_first() {
+                Some((Header(header), after_header)) => {
+                    rest = after_header;
+                    while let Some((AttributeEntry(name, value), after_entry)) = rest.split_first() {
+                        self.attribute_entry(name, value);
+                        rest = after_entry;
+                    }
+                    Some(header)
+                },
+                _ => None,
+            };
+        let mut toc = self.toc(nodes);
+        if self.attributes().get("toc") == Some("macro") {
+            *self.toc_macro() = Some(toc);
+            toc = Empty;
+        }
+        match header {
+            Some(header) => (self.header(header, toc), rest),
+            None => (toc, rest),
+        }
+    }
+
+    /// Generate an example block, which can be expanded and collapsed with the `collapsible`
+    /// option, and is then expanded at first with the `open` option.
+    fn example(&mut self, blocks: &[Block], attributes: &[Attribute]) -> Html {
+        if has_option(attributes, "collapsible") {
+            let content = div_a(attr! { class = "content" }, self.blocks(blocks));
+            let summary =
+                match named(attributes, "title") {
+                    Some(title) => self.inline(title),
+                    None => SingleTextNode("Details".to_string()),
+                };
+            let mut details_attributes = find_id_attribute(attributes).map_or_else(String::new, |id| attr! { id = id });
+            let classes = classes("", attributes);
+            if !classes.is_empty() {
+                if !details_attributes.is_empty() {
+                    details_attributes.push(' ');
+                }
+                details_attributes.push_str(&attr! { class = classes.trim_start() });
+            }
+            if has_option(attributes, "open") {
+                if !details_attributes.is_empty() {
+                    details_attributes.push(' ');
+                }
+                details_attributes.push_str("open");
+            }
+            let summary = summary_a(attr! { class = "title" }, summary);
+            return details_a(details_attributes, Seq(Box::new(summary), Box::new(content)));
+        }
+        // The caption is counted before the nested blocks, which follow it in the document.
+        let title = self.captioned_title("example", attributes);
+        let content = div_a(attr! { class = "content" }, self.blocks(blocks));
+        div_a(block_attributes("exampleblock", attributes), Seq(Box::new(title), Box::new(content)))
+    }
+
+    /// Check whether the icons are font icons, with `:icons: font`, like the Font Awesome ones.
+    fn has_font_icons(&mut self) -> bool {
+        self.attributes().get("icons") == Some("font")
+    }
+
+    /// Generate the header, with the table of contents `toc` after its details.
+    fn header(&mut self, header: &Header, toc: Html) -> Html {
+        let title = h_a(1, String::new(), self.text(&header.title));
+        let mut details = vec![];
+        for (index, author) in header.authors.iter().enumerate() {
+            details.push(self.author(author, index + 1));
+        }
+        if let Some(ref revnumber) = header.revnumber {
+            let comma = if header.revdate.is_some() { "," } else { "" };
+            let version = SingleTextNode(format!("version {}{}", escape(revnumber), comma));
+            details.push(span_a(attr! { id = "revnumber" }, version));
+        }
+        if let Some(ref revdate) = header.revdate {
+            if header.revnumber.is_some() {
+                details.push(SingleTextNode(" ".to_string()));
+            }
+            details.push(span_a(attr! { id = "revdate" }, SingleTextNode(escape(revdate))));
+        }
+        if let Some(ref revremark) = header.revremark {
+            details.push(Br);
+            details.push(span_a(attr! { id = "revremark" }, SingleTextNode(escape(revremark))));
+        }
+        let header =
+            if details.is_empty() {
+                title
+            }
+            else {
+                Seq(Box::new(title), Box::new(div_a(attr! { class = "details" }, TextNode(details))))
+            };
+        div_a(attr! { id = "header" }, Seq(Box::new(header), Box::new(toc)))
+    }
+
     fn horizontal_rule(&mut self) -> Html {
         hr()
     }
 
+    /// Generate an icon macro: a Font Awesome icon with `:icons: font`, like
+    /// `<i class="fa fa-heart fa-2x"></i>` for `icon:heart[2x]`, or its name in brackets otherwise.
+    fn icon(&mut self, name: &str, attributes: &[Attribute]) -> Html {
+        let icon =
+            if self.has_font_icons() {
+                let mut class = format!("fa fa-{}", name);
+                for &(attribute, prefix) in &[("size", "fa-"), ("flip", "fa-flip-"), ("rotate", "fa-rotate-")] {
+                    if let Some(value) = named(attributes, attribute) {
+                        class.push_str(&format!(" {}{}", prefix, value));
+                    }
+                }
+                let mut i_attributes = attr! { class = class };
+                if let Some(title) = named(attributes, "title") {
+                    i_attributes.push_str(&format!(" title=\"{}\"", escape_attribute(title)));
+                }
+                i_a(i_attributes, Empty)
+            }
+            else {
+                SingleTextNode(format!("[{}&#93;", escape(name)))
+            };
+        span_a(attr! { class = "icon" }, icon)
+    }
+
+    /// Generate a block image, whose title has a `Figure N.` caption.
+    /// The alternate text defaults to the name of the file of the `target`, like `sunset` for
+    /// `images/sunset.jpg`.
+    fn image(&mut self, target: &str, attributes: &[Attribute]) -> Html {
+        let alt = named(attributes, "alt").map_or_else(|| default_alt(target), str::to_string);
+        let mut img_attributes = attr! { src = target, alt = alt };
+        for name in &["width", "height"] {
+            if let Some(value) = named(attributes, name) {
+                img_attributes.push(' ');
+                img_attributes.push_str(&format!("{}=\"{}\"", name, escape_attribute(value)));
+            }
+        }
+        let div_attributes = block_attributes("imageblock", attributes);
+        let content = div_a(attr! { class = "content" }, img(img_attributes));
+        div_a(div_attributes, Seq(Box::new(content), Box::new(self.captioned_title("figure", attributes))))
+    }
+
+    /// Generate some text which is not part of a text node, like the title of a block.
+    fn inline(&mut self, content: &str) -> Html {
+        let attributes = self.attributes().clone();
+        match parser::inline_text(content, &attributes) {
+            Ok(text) => self.text(&text),
+            Err(_) => SingleTextNode(escape(content)),
+        }
+    }
+
+    /// Generate an index term: an anchor for the index followed by the term when it is visible,
+    /// or nothing otherwise.
+    fn index_term(&mut self, id: &str, terms: &[String], visible: bool) -> Html {
+        if !visible {
+            return Empty;
+        }
+        Seq(Box::new(A(id.to_string())), Box::new(SingleTextNode(escape(&terms[0]))))
+    }
+
     fn item(&mut self, item: &Item) -> Html {
         match *item {
-            Item::Mark(ref text, ref attributes) => self.mark(text, attributes),
+            Item::Button(ref label) => self.button(label),
+            Item::Icon { ref name, ref attributes } => self.icon(name, attributes),
+            Item::IndexTerm { ref id, ref terms, visible } => self.index_term(id, terms, visible),
+            Item::Kbd(ref keys) => self.kbd(keys),
+            Item::Link { ref target, ref text } => self.link(target, text),
+            Item::Mark(ref text) => mark(self.text(text)),
+            Item::Menu(ref menu, ref items) => self.menu(menu, items),
+            Item::NewLine => self.newline(false),
+            Item::Passthrough { ref content, ref subs } => self.passthrough(content, subs),
+            Item::SetAttribute(ref name, ref value) => self.attribute_entry(name, value),
             Item::Space => SingleTextNode(" ".to_string()),
+            Item::Span { ref attributes, ref text } => self.span(text, attributes),
+            Item::Stem { kind, ref content } => self.stem(kind, content),
             Item::Tag(tag, ref text, ref attributes) => self.tag(tag, text, attributes),
-            Item::Word(ref text) => SingleTextNode(text.clone()),
+            Item::Word(ref word) => self.word(word),
+            Item::Xref { ref id, ref path, ref text } => self.xref(id, path, text),
         }
     }
 
-    fn mark(&mut self, text: &Text, attributes: &[Attribute]) -> Html {
-        let text = self.text(text);
-        if attributes.is_empty() {
-            mark(text)
-        } else {
-            span_a(attributes_to_string(attributes), text)
+    fn kbd(&mut self, keys: &[String]) -> Html {
+        if keys.len() == 1 {
+            return kbd(SingleTextNode(keys[0].clone()));
+        }
+        let mut children = vec![];
+        for (index, key) in keys.iter().enumerate() {
+            if index > 0 {
+                children.push(SingleTextNode("+".to_string()));
+            }
+            children.push(kbd(SingleTextNode(key.clone())));
         }
+        kbd_a(attr! { class = "keyseq" }, TextNode(children))
+    }
+
+    /// Generate a link, which is a bare link when it has no text.
+    fn link(&mut self, target: &str, text: &Option<String>) -> Html {
+        match *text {
+            Some(ref text) => a_a(attr! { href = target }, SingleTextNode(escape(text))),
+            None => a_a(attr! { href = target, class = "bare" }, SingleTextNode(escape(target))),
+        }
+    }
+
+    /// Generate a listing block, which is highlighted source code with the `source` style, like
+    /// `[source,rust]`.
+    fn listing(&mut self, content: &str, attributes: &[Attribute]) -> Html {
+        let content = self.verbatim(content, attributes);
+        let pre =
+            if style(attributes) == Some("source") {
+                let code_attributes =
+                    match positional(attributes).map(escape_attribute) {
+                        Some(language) => format!("class=\"language-{0}\" data-lang=\"{0}\"", language),
+                        None => String::new(),
+                    };
+                let linenums = has_linenums(attributes);
+                let base = if linenums { "highlight linenums" } else { "highlight" };
+                let mut pre_attributes = pre_attributes(base, attributes);
+                if let Some(start) = named(attributes, "start").filter(|_| linenums) {
+                    pre_attributes.push_str(&format!(" data-start=\"{}\"", escape_attribute(start)));
+                }
+                pre_a(pre_attributes, Tag(InlineCode, code_attributes, Box::new(content)))
+            }
+            else {
+                pre_a(pre_attributes("", attributes), content)
+            };
+        div_a(
+            block_attributes("listingblock", attributes),
+            Seq(
+                Box::new(self.captioned_title("listing", attributes)),
+                Box::new(div_a(attr! { class = "content" }, pre)),
+            ),
+        )
+    }
+
+    fn literal(&mut self, content: &str, attributes: &[Attribute]) -> Html {
+        let content = self.verbatim(content, attributes);
+        div_a(
+            block_attributes("literalblock", attributes),
+            Seq(
+                Box::new(self.block_title(attributes)),
+                Box::new(div_a(attr! { class = "content" }, pre_a(pre_attributes("", attributes), content))),
+            ),
+        )
+    }
+
+    /// Generate an audio or video block, with controls unless it has the `nocontrols` option.
+    /// The poster `youtube` or `vimeo`, like in `video::ID[youtube]`, embeds the video of this ID
+    /// from YouTube or Vimeo in an iframe instead.
+    fn media(&mut self, kind: MediaKind, target: &str, attributes: &[Attribute]) -> Html {
+        let mut size = String::new();
+        for name in &["width", "height"] {
+            if let Some(value) = named(attributes, name) {
+                size.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+            }
+        }
+        let embed =
+            match (kind, named(attributes, "poster")) {
+                (MediaKind::Video, Some("youtube")) => Some(format!("https://www.youtube.com/embed/{}?rel=0", target)),
+                (MediaKind::Video, Some("vimeo")) => Some(format!("https://player.vimeo.com/video/{}", target)),
+                _ => None,
+            };
+        let element =
+            match embed {
+                Some(src) => {
+                    let src = attr! { src = src };
+                    iframe(format!("{}{} frameborder=\"0\" allowfullscreen", src, size))
+                },
+                None => {
+                    let mut media_attributes = format!("{}{}", attr! { src = target }, size);
+                    if let Some(poster) = named(attributes, "poster") {
+                        media_attributes.push_str(&format!(" poster=\"{}\"", escape_attribute(poster)));
+                    }
+                    for option in &["autoplay", "loop", "muted"] {
+                        if has_option(attributes, option) {
+                            media_attributes.push_str(&format!(" {}", option));
+                        }
+                    }
+                    if !has_option(attributes, "nocontrols") {
+                        media_attributes.push_str(" controls");
+                    }
+                    let fallback = SingleTextNode(format!("Your browser does not support the {} tag.", kind.name()));
+                    match kind {
+                        MediaKind::Audio => audio_a(media_attributes, fallback),
+                        MediaKind::Video => video_a(media_attributes, fallback),
+                    }
+                },
+            };
+        let div_attributes = block_attributes(&format!("{}block", kind.name()), attributes);
+        div_a(
+            div_attributes,
+            Seq(Box::new(self.block_title(attributes)), Box::new(div_a(attr! { class = "content" }, element))),
+        )
+    }
+
+    /// Generate a menu UI macro, as a breadcrumb of the menu, its submenus and its item.
+    fn menu(&mut self, menu: &str, items: &[String]) -> Html {
+        let menu = span_a(attr! { class = "menu" }, SingleTextNode(menu.to_string()));
+        if items.is_empty() {
+            return menu;
+        }
+        let mut children = vec![menu];
+        for (index, item) in items.iter().enumerate() {
+            let class = if index + 1 == items.len() { "menuitem" } else { "submenu" };
+            children.push(SingleTextNode("&#160;&#9656;&#160;".to_string()));
+            children.push(span_a(attr! { class = class }, SingleTextNode(item.clone())));
+        }
+        span_a(attr! { class = "menuseq" }, TextNode(children))
+    }
+
+    /// Generate the end of a line of a paragraph, which is a line break with the `hardbreaks` option
+    /// of the paragraph, or with the `hardbreaks-option` attribute, or its older name `hardbreaks`.
+    fn newline(&mut self, hardbreaks: bool) -> Html {
+        if hardbreaks || self.attributes().is_set("hardbreaks-option") || self.attributes().is_set("hardbreaks") {
+            return Seq(Box::new(Br), Box::new(SingleTextNode("\n".to_string())));
+        }
+        SingleTextNode("\n".to_string())
     }
 
     fn node(&mut self, node: &Node) -> Html {
+        if let Some((id, text)) = reference(node) {
+            self.references().insert(id, text);
+        }
         match *node {
+            Admonition(kind, ref blocks, ref attributes) => self.admonition(kind, blocks, attributes),
+            AttributeEntry(ref name, ref value) => self.attribute_entry(name, value),
+            DescriptionList(ref items, ref attributes) => self.description_list(items, attributes),
+            Example(ref blocks, ref attributes) => self.example(blocks, attributes),
+            Header(ref header) => self.header(header, Empty),
             HorizontalRule => self.horizontal_rule(),
+            Image { ref attributes, ref target } => self.image(target, attributes),
             PageBreak => self.page_break(),
-            Paragraph(ref text) => self.paragraph(text),
+            Listing(ref content, ref attributes) => self.listing(content, attributes),
+            Literal(ref content, ref attributes) => self.literal(content, attributes),
+            Media { ref attributes, kind, ref target } => self.media(kind, target, attributes),
+            Paragraph(ref text, ref attributes) => self.paragraph(text, attributes),
+            PassthroughBlock { ref content, ref subs, .. } => self.passthrough(content, subs),
+            Preamble(ref blocks) => self.preamble(blocks),
+            Raw(ref content, _) => SingleTextNode(content.clone()),
+            Section(ref section) => self.section(section),
+            Stem { ref attributes, kind, ref content } => self.stem_block(kind, content, attributes),
+            Node::Table(ref table) => self.table(table),
+            Toc(_) => self.toc_macro().take().unwrap_or(Empty),
+            UnorderedList(ref items, ref attributes) => self.unordered_list(items, attributes),
         }
     }
 
@@ -94,17 +738,318 @@ pub trait HtmlGen {
         )
     }
 
-    fn paragraph(&mut self, text: &Text) -> Html {
+    /// Generate the content of a passthrough macro as is, after the substitutions of its `subs`:
+    /// the special characters, then the attribute references.
+    fn passthrough(&mut self, content: &str, subs: &[String]) -> Html {
+        let has_sub = |short: &str, long: &str| subs.iter().any(|sub| sub == short || sub == long);
+        let mut content = content.to_string();
+        if has_sub("c", "specialchars") {
+            content = escape(&content);
+        }
+        if has_sub("a", "attributes") {
+            content = self.substitute_attributes(&content, false);
+        }
+        SingleTextNode(content)
+    }
+
+    fn paragraph(&mut self, text: &Text, attributes: &[Attribute]) -> Html {
+        let hardbreaks = has_option(attributes, "hardbreaks");
+        let mut texts = vec![];
+        for item in &text.items {
+            match *item {
+                Item::NewLine => texts.push(self.newline(hardbreaks)),
+                _ => texts.push(self.item(item)),
+            }
+        }
+        let text = TextNode(texts);
+        div_a(
+            block_attributes("paragraph", attributes),
+            Seq(Box::new(self.block_title(attributes)), Box::new(p(text))),
+        )
+    }
+
+    /// Generate the preamble of a document, whose blocks are in a `sectionbody` like the ones of a
+    /// section, unless the doctype is not `article`.
+    fn preamble(&mut self, blocks: &[Block]) -> Html {
+        let blocks = self.blocks(blocks);
+        if self.attributes().get("doctype").is_some_and(|doctype| doctype != "article") {
+            return blocks;
+        }
+        div_a(attr! { id = "preamble" }, div_a(attr! { class = "sectionbody" }, blocks))
+    }
+
+    /// Generate a section, whose heading links to itself when `:sectlinks:` is set, and starts with
+    /// an empty anchor, shown on hover, when `:sectanchors:` is set.
+    fn section(&mut self, section: &Section) -> Html {
+        let mut heading_attributes =
+            if section.id.is_empty() {
+                String::new()
+            }
+            else {
+                attr! { id = section.id }
+            };
+        if section.is_discrete() {
+            let title = self.text(&section.title);
+            if !heading_attributes.is_empty() {
+                heading_attributes.push(' ');
+            }
+            heading_attributes.push_str(&attr! { class = classes("discrete", &section.attributes) });
+            return h_a(section.level as u8 + 1, heading_attributes, title);
+        }
+        let mut title = self.text(&section.title);
+        if let Some(number) = self.section_number(section.level) {
+            title = Seq(Box::new(SingleTextNode(number)), Box::new(title));
+        }
+        if self.attributes().is_set("sectlinks") && !section.id.is_empty() {
+            title = a_a(attr! { class = "link", href = format!("#{}", section.id) }, title);
+        }
+        if self.attributes().is_set("sectanchors") && !section.id.is_empty() {
+            let anchor = a_a(attr! { class = "anchor", href = format!("#{}", section.id) }, Empty);
+            title = Seq(Box::new(anchor), Box::new(title));
+        }
+        let heading = h_a(section.level as u8 + 1, heading_attributes, title);
+        let mut blocks = vec![];
+        for block in &section.blocks {
+            blocks.push(self.node(&block.node));
+        }
+        let mut content = TextNode(blocks);
+        if section.level == 1 {
+            content = div_a(attr! { class = "sectionbody" }, content);
+        }
+        div_a(
+            attr! { class = classes(&format!("sect{}", section.level), &section.attributes) },
+            Seq(Box::new(heading), Box::new(content)),
+        )
+    }
+
+    /// Get the number of a section, like `2.1. `, when `:sectnums:` is set and the level of the
+    /// section is at most `:sectnumlevels:`, which defaults to 3.
+    fn section_number(&mut self, level: usize) -> Option<String> {
+        if !self.attributes().is_set("sectnums") && !self.attributes().is_set("numbered") {
+            return None;
+        }
+        let levels = self.attributes().get("sectnumlevels")
+            .and_then(|levels| levels.parse().ok())
+            .unwrap_or(3);
+        if level > levels {
+            return None;
+        }
+        let numbers = self.section_numbers();
+        numbers.resize(level, 0);
+        numbers[level - 1] += 1;
+        let mut number = String::new();
+        for part in numbers.iter() {
+            number.push_str(&format!("{}.", part));
+        }
+        number.push(' ');
+        Some(number)
+    }
+
+    fn stem(&mut self, kind: StemKind, content: &str) -> Html {
+        let math =
+            match self.stem_notation(kind) {
+                StemKind::LatexMath => format!("\\({}\\)", escape(content)),
+                _ => format!("\\${}\\$", escape(content)),
+            };
+        SingleTextNode(math)
+    }
+
+    /// Generate a span of text with the ID and the roles of its attribute list.
+    fn span(&mut self, text: &Text, attributes: &[Attribute]) -> Html {
         let text = self.text(text);
+        span_a(attributes_to_string(attributes), text)
+    }
+
+    /// Generate the body of a standalone document: its header, its content and its footer, with the
+    /// version of the document.
+    /// The header is omitted when `:noheader:` is set and the footer when `:nofooter:` is set.
+    fn standalone(&mut self, nodes: &[Node]) -> Html {
+        let (header, rest) = self.document_header(nodes);
+        let header = if self.attributes().is_set("noheader") { Empty } else { header };
+        let mut children = vec![];
+        for node in rest {
+            children.push(self.node(node));
+        }
+        let content = div_a(attr! { id = "content" }, TextNode(children));
+        let footer =
+            if self.attributes().is_set("nofooter") {
+                Empty
+            }
+            else {
+                let version =
+                    match nodes.first() {
+                        Some(Header(node::Header { revnumber: Some(ref revnumber), .. })) =>
+                            SingleTextNode(format!("Version {}", escape(revnumber))),
+                        _ => Empty,
+                    };
+                div_a(attr! { id = "footer" }, div_a(attr! { id = "footer-text" }, version))
+            };
+        TextNode(vec![header, content, footer])
+    }
+
+    /// Generate a math block, whose title has an `Equation N.` caption when `:eqnums:` is set.
+    fn stem_block(&mut self, kind: StemKind, content: &str, attributes: &[Attribute]) -> Html {
+        let math =
+            match self.stem_notation(kind) {
+                StemKind::LatexMath => format!("\\[{}\\]", escape(content)),
+                _ => format!("\\${}\\$", escape(content)),
+            };
+        let title =
+            match named(attributes, "title") {
+                Some(title) if self.attributes().is_set("eqnums") =>
+                    div_a(attr! { class = "title" }, self.captioned_text("equation", title, attributes)),
+                _ => self.block_title(attributes),
+            };
         div_a(
-            attr! { class = "paragraph" },
-            p(text),
+            attr! { class = "stemblock" },
+            Seq(Box::new(title), Box::new(div_a(attr! { class = "content" }, SingleTextNode(math)))),
+        )
+    }
+
+    /// Resolve the notation of a stem macro or block from the `:stem:` attribute, which defaults to
+    /// AsciiMath.
+    fn stem_notation(&mut self, kind: StemKind) -> StemKind {
+        if kind != StemKind::Stem {
+            return kind;
+        }
+        match self.attributes().get("stem") {
+            Some("latexmath") | Some("latex") | Some("tex") => StemKind::LatexMath,
+            _ => StemKind::AsciiMath,
+        }
+    }
+
+    /// Generate a table, whose width fits its content with the `autowidth` option and whose rows
+    /// are striped with the `stripes` attribute, like `stripes=odd`, or the `:table-stripes:`
+    /// document attribute.
+    /// With the `header` option, the first row is in the `thead` element, and with the `footer`
+    /// option, the last row is in the `tfoot` element.
+    fn table(&mut self, table: &node::Table) -> Html {
+        // The caption is counted before the blocks of the cells, which follow it in the document.
+        let caption =
+            match named(&table.attributes, "title") {
+                Some(title) =>
+                    caption_a(attr! { class = "title" }, self.captioned_text("table", title, &table.attributes)),
+                None => Empty,
+            };
+        let autowidth = has_option(&table.attributes, "autowidth");
+        let mut cols = vec![];
+        for width in column_widths(&table.columns) {
+            if autowidth {
+                cols.push(col(String::new()));
+            }
+            else {
+                cols.push(col(attr! { style = format!("width: {}%;", width) }));
+            }
+        }
+        let mut rows = &table.rows[..];
+        let mut head = Empty;
+        if has_option(&table.attributes, "header") {
+            if let Some((first, rest)) = rows.split_first() {
+                head = thead(self.table_row(first, true));
+                rows = rest;
+            }
+        }
+        let mut foot = Empty;
+        if has_option(&table.attributes, "footer") {
+            if let Some((last, rest)) = rows.split_last() {
+                foot = tfoot(self.table_row(last, false));
+                rows = rest;
+            }
+        }
+        let mut body = vec![];
+        for row in rows {
+            body.push(self.table_row(row, false));
+        }
+        let body =
+            if body.is_empty() {
+                Empty
+            }
+            else {
+                tbody(TextNode(body))
+            };
+        let mut class = "tableblock frame-all grid-all".to_string();
+        let stripes = named(&table.attributes, "stripes").map(str::to_string)
+            .or_else(|| self.attributes().get("table-stripes").map(str::to_string));
+        if let Some(stripes) = stripes {
+            class.push_str(&format!(" stripes-{}", stripes));
+        }
+        class.push_str(if autowidth { " fit-content" } else { " stretch" });
+        table_a(
+            block_attributes(&class, &table.attributes),
+            TextNode(vec![caption, colgroup(TextNode(cols)), head, body, foot]),
         )
     }
 
+    /// Generate a table cell, which is a `th` element in the header row or with the header style.
+    /// The paragraphs of a cell in the header row are not wrapped in `p` elements.
+    fn table_cell(&mut self, cell: &Cell, in_header: bool) -> Html {
+        let class = format!("tableblock halign-{} valign-{}", cell.halign.name(), cell.valign.name());
+        let mut attributes = attr! { class = class };
+        if cell.colspan > 1 {
+            attributes.push(' ');
+            attributes.push_str(&attr! { colspan = cell.colspan });
+        }
+        if cell.rowspan > 1 {
+            attributes.push(' ');
+            attributes.push_str(&attr! { rowspan = cell.rowspan });
+        }
+        let content =
+            match cell.content {
+                CellContent::Blocks(ref blocks) => div(self.blocks(blocks)),
+                CellContent::Literal(ref source) =>
+                    div_a(attr! { class = "literal" }, pre(SingleTextNode(source.clone()))),
+                CellContent::Paragraphs(ref paragraphs) => {
+                    let mut texts = vec![];
+                    for paragraph in paragraphs {
+                        let text = self.text(paragraph);
+                        // The header cells have no paragraphs, so their paragraphs are joined with a space.
+                        if in_header {
+                            if !texts.is_empty() {
+                                texts.push(SingleTextNode(" ".to_string()));
+                            }
+                            texts.push(text);
+                            continue;
+                        }
+                        let text =
+                            match cell.style {
+                                CellStyle::Emphasis => Tag(Italic, String::new(), Box::new(text)),
+                                CellStyle::Monospace => Tag(InlineCode, String::new(), Box::new(text)),
+                                CellStyle::Strong => Tag(Bold, String::new(), Box::new(text)),
+                                CellStyle::Verse => {
+                                    texts.push(text);
+                                    continue;
+                                },
+                                _ => text,
+                            };
+                        texts.push(p_a(attr! { class = "tableblock" }, text));
+                    }
+                    if cell.style == CellStyle::Verse {
+                        div_a(attr! { class = "verse" }, TextNode(texts))
+                    } else {
+                        TextNode(texts)
+                    }
+                },
+            };
+        if in_header || cell.style == CellStyle::Header {
+            th_a(attributes, content)
+        } else {
+            td_a(attributes, content)
+        }
+    }
+
+    /// Generate a table row, which is in the header of the table when `in_header` is `true`.
+    fn table_row(&mut self, row: &[Cell], in_header: bool) -> Html {
+        let mut cells = vec![];
+        for cell in row {
+            cells.push(self.table_cell(cell, in_header));
+        }
+        tr(TextNode(cells))
+    }
+
     fn tag(&mut self, tag: Tag, text: &Text, attributes: &[Attribute]) -> Html {
         let text = self.text(text);
-        let tag = Tag(tag, attributes_to_string(attributes), Box::new(text));
+        let roles = attributes.iter().filter(|attribute| !is_id(attribute));
+        let tag = Tag(tag, attributes_to_string(roles), Box::new(text));
         if let Some(id) = find_id_attribute(attributes) {
             Seq(Box::new(A(id)), Box::new(tag))
         } else {
@@ -112,6 +1057,40 @@ pub trait HtmlGen {
         }
     }
 
+    /// Replace the references to the document attributes, like `{nbsp}`, by their value, which is
+    /// escaped when `escaped` is true, unless it is the HTML of an intrinsic attribute.
+    /// The references to attributes which are not set are kept as is.
+    fn substitute_attributes(&mut self, text: &str, escaped: bool) -> String {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest.find('}')
+                .map(|end| &rest[1..end])
+                .filter(|name| is_attribute_name(name))
+                .and_then(|name| self.attributes().get(name).map(|value| (name, value.to_string())));
+            match value {
+                Some((name, value)) => {
+                    let length = name.len();
+                    if escaped && !self.attributes().is_intrinsic(name) {
+                        result.push_str(&escape(&value));
+                    }
+                    else {
+                        result.push_str(&value);
+                    }
+                    rest = &rest[length + 2..];
+                },
+                None => {
+                    result.push('{');
+                    rest = &rest[1..];
+                },
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
     fn text(&mut self, text: &Text) -> Html {
         let mut texts = vec![];
         for item in &text.items {
@@ -119,60 +1098,489 @@ pub trait HtmlGen {
         }
         TextNode(texts)
     }
+
+    fn word(&mut self, word: &str) -> Html {
+        let word = escape(word);
+        SingleTextNode(self.substitute_attributes(&word, true))
+    }
+
+    /// Generate the table of contents of the sections found in `nodes`, when `:toc:` is set.
+    /// It lists the sections up to the level `:toclevels:`, which defaults to 2, and has the
+    /// `doc-toc` ARIA role when `:aria-roles:` is set.
+    /// With `:toc: left` or `:toc: right`, it has the `toc2` class, to be shown in a sidebar.
+    fn toc(&mut self, nodes: &[Node]) -> Html {
+        if !self.attributes().is_set("toc") {
+            return Empty;
+        }
+        let levels = self.attributes().get("toclevels")
+            .and_then(|levels| levels.parse().ok())
+            .unwrap_or(2);
+        let title = escape(self.attributes().get("toc-title").unwrap_or("Table of Contents"));
+        let title = self.substitute_attributes(&title, true);
+        let entries = self.toc_entries(nodes.iter().collect(), 1, levels);
+        // The numbers of the sections are generated again with the sections themselves.
+        self.section_numbers().clear();
+        let class = if toc_sidebar(self.attributes()).is_some() { "toc2" } else { "toc" };
+        let toc_attributes =
+            if self.attributes().is_set("aria-roles") {
+                attr! { id = "toc", class = class, role = "doc-toc" }
+            }
+            else {
+                attr! { id = "toc", class = class }
+            };
+        div_a(
+            toc_attributes,
+            Seq(
+                Box::new(div_a(attr! { id = "toctitle" }, SingleTextNode(title))),
+                Box::new(entries),
+            ),
+        )
+    }
+
+    /// Generate the entries of the table of contents for the sections of `level` found in `nodes`.
+    fn toc_entries(&mut self, nodes: Vec<&Node>, level: usize, levels: usize) -> Html {
+        let mut entries = vec![];
+        for node in nodes {
+            if let Section(ref section) = *node {
+                if section.is_discrete() {
+                    continue;
+                }
+                let mut title = self.text(&section.title);
+                if let Some(number) = self.section_number(section.level) {
+                    title = Seq(Box::new(SingleTextNode(number)), Box::new(title));
+                }
+                let link =
+                    if section.id.is_empty() {
+                        title
+                    }
+                    else {
+                        a_a(attr! { href = format!("#{}", section.id) }, title)
+                    };
+                let has_subsections = section.blocks.iter()
+                    .any(|block| matches!(block.node, Section(ref section) if !section.is_discrete()));
+                if level < levels && has_subsections {
+                    let blocks = section.blocks.iter().map(|block| &block.node).collect();
+                    let children = self.toc_entries(blocks, level + 1, levels);
+                    entries.push(li(Seq(Box::new(link), Box::new(children))));
+                }
+                else {
+                    entries.push(li(link));
+                }
+            }
+        }
+        if entries.is_empty() {
+            return Empty;
+        }
+        ul_a(attr! { class = format!("sectlevel{}", level) }, TextNode(entries))
+    }
+
+    fn unordered_list(&mut self, items: &[ListItem], attributes: &[Attribute]) -> Html {
+        let mut children = vec![];
+        for item in items {
+            let mut content = vec![p(self.text(&item.text))];
+            for block in &item.blocks {
+                content.push(self.node(&block.node));
+            }
+            children.push(li(TextNode(content)));
+        }
+        div_a(
+            attr! { class = classes("ulist", attributes) },
+            Seq(Box::new(self.block_title(attributes)), Box::new(ul(TextNode(children)))),
+        )
+    }
+
+    /// Generate the content of a verbatim block, which is kept as is unless the `subs` attribute
+    /// enables the quotes substitutions, like `subs="+quotes"`: the formatting of each line is then
+    /// generated.
+    fn verbatim(&mut self, content: &str, attributes: &[Attribute]) -> Html {
+        if !enables_quotes(attributes) {
+            return SingleTextNode(escape(content));
+        }
+        let mut lines = vec![];
+        for (index, line) in content.lines().enumerate() {
+            if index > 0 {
+                lines.push(SingleTextNode("\n".to_string()));
+            }
+            lines.push(self.inline(line));
+        }
+        TextNode(lines)
+    }
+
+    /// Generate a cross reference, whose text defaults to the text of the references to its ID.
+    /// A cross reference to another document links to the file generated from it, with the
+    /// `outfilesuffix` attribute, like `other.html#id` for `other.adoc#id`.
+    fn xref(&mut self, id: &str, path: &Option<String>, text: &Option<String>) -> Html {
+        let href =
+            match *path {
+                Some(ref path) => {
+                    let suffix = self.attributes().get("outfilesuffix").unwrap_or(".html");
+                    let path = path.strip_suffix(".adoc")
+                        .map_or_else(|| path.clone(), |path| format!("{}{}", path, suffix));
+                    if id.is_empty() {
+                        path
+                    }
+                    else {
+                        format!("{}#{}", path, id)
+                    }
+                },
+                None => format!("#{}", id),
+            };
+        let text =
+            match (text, path) {
+                (Some(text), _) => text.clone(),
+                (None, Some(_)) => href.clone(),
+                (None, None) => self.references().get(id).cloned().unwrap_or_else(|| format!("[{}]", id)),
+            };
+        a_a(attr! { href = href }, SingleTextNode(escape(&text)))
+    }
+}
+
+impl<G: HtmlGen> Backend for G {
+    fn parser_attributes(&mut self) -> AttributeRegistry {
+        self.attributes().clone()
+    }
+
+    fn id_style(&self) -> IdStyle {
+        HtmlGen::id_style(self)
+    }
+
+    fn write_document(&mut self, nodes: &[Node], mut writer: &mut dyn Write) -> Result<()> {
+        gen_document(self, nodes, &mut writer)
+    }
 }
 
-impl HtmlGen for Generator {}
+impl HtmlGen for Generator {
+    fn attributes(&mut self) -> &mut AttributeRegistry {
+        &mut self.attributes
+    }
+
+    fn captions(&mut self) -> &mut CaptionRegistry {
+        &mut self.captions
+    }
+
+    fn id_style(&self) -> IdStyle {
+        self.id_style
+    }
+
+    fn references(&mut self) -> &mut HashMap<String, String> {
+        &mut self.references
+    }
+
+    fn section_numbers(&mut self) -> &mut Vec<usize> {
+        &mut self.section_numbers
+    }
+
+    fn toc_macro(&mut self) -> &mut Option<Html> {
+        &mut self.toc_macro
+    }
+}
 
 /// Represent an HTML node with its children.
 pub enum Html {
     A(Id),
+    Anchor(String, Box<Html>),
+    Audio(String, Box<Html>),
+    B(String, Box<Html>),
+    Br,
+    Caption(String, Box<Html>),
+    Col(String),
+    Colgroup(Box<Html>),
+    Dd(Box<Html>),
+    Details(String, Box<Html>),
     Div(String, Box<Html>),
+    Dl(Box<Html>),
+    Dt(String, Box<Html>),
     Empty,
+    H(u8, String, Box<Html>),
+    I(String, Box<Html>),
     Hr,
+    Iframe(String),
+    Img(String),
+    Kbd(String, Box<Html>),
+    Li(Box<Html>),
     Mark(Box<Html>),
-    P(Box<Html>),
+    P(String, Box<Html>),
+    Pre(String, Box<Html>),
     Seq(Box<Html>, Box<Html>),
     SingleTextNode(String),
     Span(String, Box<Html>),
+    Summary(String, Box<Html>),
+    Table(String, Box<Html>),
     Tag(Tag, String, Box<Html>),
+    Tbody(Box<Html>),
+    Td(String, Box<Html>),
     TextNode(Vec<Html>),
+    Tfoot(Box<Html>),
+    Th(String, Box<Html>),
+    Thead(Box<Html>),
+    Tr(Box<Html>),
+    Ul(String, Box<Html>),
+    Video(String, Box<Html>),
+    /// A void element, which has no children nor closing tag, like `<input type="checkbox"/>`.
+    Void(&'static str, String),
 }
 
 impl Html {
-    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+    /// Format the HTML code in `writer`, like a `String`, with the void elements self-closed, like
+    /// `<br/>`.
+    pub fn format<W: fmt::Write>(&self, writer: &mut W) -> fmt::Result {
+        self.format_with_syntax(writer, true)
+    }
+
+    /// Format the HTML code in `writer`, with the void elements, like `img`, self-closed when `xhtml`
+    /// is true, like `<img src="a.png"/>`, and without a closing tag otherwise, like `<img src="a.png">`.
+    pub fn format_with_syntax<W: fmt::Write>(&self, writer: &mut W, xhtml: bool) -> fmt::Result {
         match *self {
-            A(ref id) => tag_a_without_child("a", &attr! { id = id }, writer),
-            Div(ref attributes, ref children) => tag_a("div", attributes, children, writer),
+            A(ref id) => {
+                write_text("<a id=\"", writer)?;
+                write_escaped(id, true, writer)?;
+                write_text("\"></a>", writer)
+            },
+            Anchor(ref attributes, ref children) => tag_a("a", attributes, children, xhtml, writer),
+            Audio(ref attributes, ref children) => tag_a("audio", attributes, children, xhtml, writer),
+            B(ref attributes, ref children) => tag_a("b", attributes, children, xhtml, writer),
+            Br => void("br", "", xhtml, writer),
+            Caption(ref attributes, ref children) => tag_a("caption", attributes, children, xhtml, writer),
+            Col(ref attributes) => void("col", attributes, xhtml, writer),
+            Colgroup(ref children) => tag("colgroup", children, xhtml, writer),
+            Dd(ref children) => tag("dd", children, xhtml, writer),
+            Details(ref attributes, ref children) => tag_a("details", attributes, children, xhtml, writer),
+            Div(ref attributes, ref children) => tag_a("div", attributes, children, xhtml, writer),
+            Dl(ref children) => tag("dl", children, xhtml, writer),
+            Dt(ref attributes, ref children) => tag_a("dt", attributes, children, xhtml, writer),
             Empty => Ok(()),
-            Hr => write_text("<hr/>", writer),
-            Mark(ref children) => tag("mark", children, writer),
-            P(ref children) => tag("p", children, writer),
+            H(level, ref attributes, ref children) => heading(level, attributes, children, xhtml, writer),
+            I(ref attributes, ref children) => tag_a("i", attributes, children, xhtml, writer),
+            Hr => void("hr", "", xhtml, writer),
+            Iframe(ref attributes) => tag_a_without_child("iframe", attributes, writer),
+            Img(ref attributes) => void("img", attributes, xhtml, writer),
+            Kbd(ref attributes, ref children) => tag_a("kbd", attributes, children, xhtml, writer),
+            Li(ref children) => tag("li", children, xhtml, writer),
+            Mark(ref children) => tag("mark", children, xhtml, writer),
+            P(ref attributes, ref children) => tag_a("p", attributes, children, xhtml, writer),
+            Pre(ref attributes, ref children) => tag_a("pre", attributes, children, xhtml, writer),
             Seq(ref child1, ref child2) => {
-                child1.write(writer)?;
-                child2.write(writer)
+                child1.format_with_syntax(writer, xhtml)?;
+                child2.format_with_syntax(writer, xhtml)
             },
             SingleTextNode(ref text) => write_text(text, writer),
-            Span(ref attributes, ref children) => tag_a("span", attributes, children, writer),
-            Tag(ref tag, ref attributes, ref children) => tag_a(tag.to_string(), attributes, children, writer),
+            Span(ref attributes, ref children) => tag_a("span", attributes, children, xhtml, writer),
+            Summary(ref attributes, ref children) => tag_a("summary", attributes, children, xhtml, writer),
+            Html::Table(ref attributes, ref children) => tag_a("table", attributes, children, xhtml, writer),
+            Tag(ref tag, ref attributes, ref children) => tag_a(tag.to_string(), attributes, children, xhtml, writer),
+            Tbody(ref children) => tag("tbody", children, xhtml, writer),
+            Td(ref attributes, ref children) => tag_a("td", attributes, children, xhtml, writer),
             TextNode(ref nodes) => {
                 for node in nodes {
-                    node.write(writer)?;
+                    node.format_with_syntax(writer, xhtml)?;
                 }
                 Ok(())
             },
+            Tfoot(ref children) => tag("tfoot", children, xhtml, writer),
+            Th(ref attributes, ref children) => tag_a("th", attributes, children, xhtml, writer),
+            Thead(ref children) => tag("thead", children, xhtml, writer),
+            Tr(ref children) => tag("tr", children, xhtml, writer),
+            Ul(ref attributes, ref children) => tag_a("ul", attributes, children, xhtml, writer),
+            Video(ref attributes, ref children) => tag_a("video", attributes, children, xhtml, writer),
+            Void(name, ref attributes) => void(name, attributes, xhtml, writer),
         }
     }
+
+    /// Write the HTML code in `writer`, like a file.
+    /// The elements are formatted directly into the `writer`, without building the code in a string.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.write_with_syntax(writer, true)
+    }
+
+    /// Write the HTML code in `writer`, with the void elements self-closed when `xhtml` is true, like
+    /// `format_with_syntax()`.
+    pub fn write_with_syntax<W: Write>(&self, writer: &mut W, xhtml: bool) -> Result<()> {
+        write!(writer, "{}", Syntax(self, xhtml))?;
+        Ok(())
+    }
+}
+
+/// Some HTML code to format with the XHTML syntax or not.
+struct Syntax<'a>(&'a Html, bool);
+
+impl<'a> fmt::Display for Syntax<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.format_with_syntax(formatter, self.1)
+    }
 }
 
-fn attributes_to_string(attributes: &[Attribute]) -> String {
-    let mut string = String::new();
+impl fmt::Display for Html {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.format(formatter)
+    }
+}
+
+/// Convert the attributes of an inline element to HTML attributes: the style and the roles are
+/// merged in a single class attribute, like `class="a b c"` for `[.a.b.c]`.
+/// The IDs come first, and the attributes are written directly in the resulting string, since this
+/// is done for most inline elements.
+fn attributes_to_string<'a, I: IntoIterator<Item=&'a Attribute>>(attributes: I) -> String {
+    let mut html_attributes = String::new();
+    let mut classes = String::new();
     for attribute in attributes {
         match *attribute {
-            Attribute::Id(ref id) => string.push_str(&format!("id=\"{}\"", id)), // TODO: needs space around?
-            Role(ref role) => string.push_str(&format!("class=\"{}\"", role)), // TODO: needs space around?
+            Attribute::Id(ref id) => {
+                if !html_attributes.is_empty() {
+                    html_attributes.push(' ');
+                }
+                html_attributes.push_str("id=\"");
+                html_attributes.push_str(&escape_attribute(id));
+                html_attributes.push('"');
+            },
+            Role(ref role) | Style(ref role) => {
+                if !classes.is_empty() {
+                    classes.push(' ');
+                }
+                classes.push_str(role);
+            },
+            Attribute::Named(..) | Attribute::Positional(_) => (),
+        }
+    }
+    if !classes.is_empty() {
+        if !html_attributes.is_empty() {
+            html_attributes.push(' ');
+        }
+        html_attributes.push_str("class=\"");
+        html_attributes.push_str(&escape_attribute(&classes));
+        html_attributes.push('"');
+    }
+    html_attributes
+}
+
+/// Create an a element with attributes.
+pub fn a_a(attributes: String, children: Html) -> Html {
+    Anchor(attributes, Box::new(children))
+}
+
+/// Create an audio element with attributes.
+pub fn audio_a(attributes: String, children: Html) -> Html {
+    Audio(attributes, Box::new(children))
+}
+
+/// Create a b element with attributes.
+pub fn b_a(attributes: String, children: Html) -> Html {
+    B(attributes, Box::new(children))
+}
+
+/// Get the HTML attributes of the element wrapping a block: its ID, if any, and its classes, which
+/// are the `base` one followed by its roles.
+fn block_attributes(base: &str, attributes: &[Attribute]) -> String {
+    let mut block_attributes = find_id_attribute(attributes).map_or_else(String::new, |id| attr! { id = id });
+    if !block_attributes.is_empty() {
+        block_attributes.push(' ');
+    }
+    block_attributes.push_str(&attr! { class = classes(base, attributes) });
+    block_attributes
+}
+
+/// Get the ID of a captioned block with the text of the cross references to it, like `Table 1` for
+/// its `label` and its `number`, unless it has a `reftext`.
+fn caption_reference(attributes: &[Attribute], label: &str, number: usize) -> Option<(String, String)> {
+    if named(attributes, "reftext").is_some() {
+        return None;
+    }
+    Some((find_id_attribute(attributes)?, format!("{} {}", label, number)))
+}
+
+/// Get the kind of the caption of a block, like `table`, with its attributes, when it has a title.
+/// A collapsible example has no caption.
+fn captioned_block(node: &Node) -> Option<(&'static str, &[Attribute])> {
+    let (kind, attributes) =
+        match *node {
+            Example(_, ref attributes) if !has_option(attributes, "collapsible") => ("example", attributes),
+            Image { ref attributes, .. } => ("figure", attributes),
+            Listing(_, ref attributes) => ("listing", attributes),
+            Node::Table(ref table) => ("table", &table.attributes),
+            _ => return None,
+        };
+    named(attributes, "title")?;
+    Some((kind, attributes))
+}
+
+/// Get the classes of a block: its base class followed by its roles, and by the `breakable` and
+/// `unbreakable` options, which tell whether a page break can happen in the block.
+fn classes(base: &str, attributes: &[Attribute]) -> String {
+    let mut classes = base.to_string();
+    for attribute in attributes {
+        if let Role(ref role) = *attribute {
+            classes.push(' ');
+            classes.push_str(role);
         }
     }
-    string
+    for option in &["breakable", "unbreakable"] {
+        if has_option(attributes, option) {
+            classes.push(' ');
+            classes.push_str(option);
+        }
+    }
+    classes
+}
+
+/// Create a br element.
+pub fn br() -> Html {
+    Br
+}
+
+/// Create a col element with attributes.
+pub fn col(attributes: String) -> Html {
+    Col(attributes)
+}
+
+/// Create a caption element with attributes.
+pub fn caption_a(attributes: String, children: Html) -> Html {
+    Caption(attributes, Box::new(children))
+}
+
+/// Create a colgroup element.
+pub fn colgroup(children: Html) -> Html {
+    Colgroup(Box::new(children))
+}
+
+/// Get the widths of the columns as percentages of the table width.
+/// The last column takes what is left, so that the widths always sum to 100%.
+/// The columns are as wide as each other when their widths are all 0, like with `cols="0,0"`.
+fn column_widths(columns: &[ColumnSpec]) -> Vec<String> {
+    let total: u64 = columns.iter().map(|column| u64::from(column.width)).sum();
+    let mut widths = vec![];
+    let mut sum = 0.0;
+    for (index, column) in columns.iter().enumerate() {
+        let (column_width, total) =
+            if total == 0 {
+                (1, columns.len() as u64)
+            }
+            else {
+                (u64::from(column.width), total)
+            };
+        let width =
+            if index == columns.len() - 1 {
+                100.0 - sum
+            } else {
+                (column_width as f64 * 1_000_000.0 / total as f64).trunc() / 10_000.0
+            };
+        sum += width;
+        widths.push(format_width(width));
+    }
+    widths
+}
+
+/// Create a dd element.
+pub fn dd(children: Html) -> Html {
+    Dd(Box::new(children))
+}
+
+/// Create a details element with attributes.
+pub fn details_a(attributes: String, children: Html) -> Html {
+    Details(attributes, Box::new(children))
+}
+
+/// Create a div element.
+pub fn div(children: Html) -> Html {
+    Div(String::new(), Box::new(children))
 }
 
 /// Create a div element with attributes.
@@ -180,6 +1588,49 @@ pub fn div_a(attributes: String, children: Html) -> Html {
     Div(attributes, Box::new(children))
 }
 
+/// Create a dl element.
+pub fn dl(children: Html) -> Html {
+    Dl(Box::new(children))
+}
+
+/// Create a dt element with attributes.
+pub fn dt_a(attributes: String, children: Html) -> Html {
+    Dt(attributes, Box::new(children))
+}
+
+/// Check whether the `subs` attribute of a verbatim block enables the quotes substitutions, like
+/// `+quotes` or `normal`.
+fn enables_quotes(attributes: &[Attribute]) -> bool {
+    named(attributes, "subs").is_some_and(|subs|
+        subs.split(',')
+            .map(str::trim)
+            .any(|sub| !sub.starts_with('-') && matches!(sub.trim_matches('+'), "quotes" | "normal")))
+}
+
+/// Get the alternate text of an image whose `target` has none: the name of its file without the
+/// extension, with spaces instead of the dashes and the underscores.
+fn default_alt(target: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let name = name.rfind('.').map_or(name, |dot| &name[..dot]);
+    name.replace(['-', '_'], " ")
+}
+
+/// Escape the HTML special characters of a text and the non-breaking spaces, so that they stay
+/// visible in the source of the output.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    let _ = write_escaped(text, false, &mut escaped);
+    escaped
+}
+
+/// Escape the special characters of the value of an HTML attribute, which include the double quotes
+/// delimiting it.
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::new();
+    let _ = write_escaped(value, true, &mut escaped);
+    escaped
+}
+
 fn find_id_attribute(attributes: &[Attribute]) -> Option<String> {
     for attribute in attributes {
         if let Attribute::Id(ref id) = *attribute {
@@ -189,19 +1640,202 @@ fn find_id_attribute(attributes: &[Attribute]) -> Option<String> {
     None
 }
 
+/// Format a width with at most 4 decimals, without trailing zeros.
+fn format_width(width: f64) -> String {
+    let width = format!("{:.4}", width);
+    width.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Create a heading element of the specified level with attributes.
+pub fn h_a(level: u8, attributes: String, children: Html) -> Html {
+    H(level, attributes, Box::new(children))
+}
+
+/// Check whether a source block has its lines numbered, with the `linenums` option or positional
+/// attribute, like `[source%linenums]` or `[source,rust,linenums]`.
+fn has_linenums(attributes: &[Attribute]) -> bool {
+    has_option(attributes, "linenums") || named(attributes, "linenums").is_some() ||
+        attributes.iter().any(|attribute| matches!(*attribute, Attribute::Positional(ref value) if value == "linenums"))
+}
+
+/// Check whether a block has the `option`, from its `options` attributes, like `options="autowidth"`
+/// or `%autowidth`.
+fn has_option(attributes: &[Attribute], option: &str) -> bool {
+    attributes.iter().any(|attribute|
+        match *attribute {
+            Attribute::Named(ref name, ref options) if name == "options" || name == "opts" =>
+                options.split(',').any(|name| name.trim() == option),
+            _ => false,
+        })
+}
+
+/// Create an i element with attributes, like a font icon.
+pub fn i_a(attributes: String, children: Html) -> Html {
+    I(attributes, Box::new(children))
+}
+
 /// Create a hr element.
 pub fn hr() -> Html {
     Hr
 }
 
+/// Check whether a name, like the one in an attribute reference, is a valid attribute name.
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|char| char.is_alphanumeric() || char == '-' || char == '_')
+}
+
+fn is_id(attribute: &Attribute) -> bool {
+    matches!(*attribute, Attribute::Id(_))
+}
+
+/// Create an iframe element with attributes.
+pub fn iframe(attributes: String) -> Html {
+    Iframe(attributes)
+}
+
+/// Create an img element with attributes.
+pub fn img(attributes: String) -> Html {
+    Img(attributes)
+}
+
+/// Create a kbd element.
+pub fn kbd(children: Html) -> Html {
+    Kbd(String::new(), Box::new(children))
+}
+
+/// Create a kbd element with attributes.
+pub fn kbd_a(attributes: String, children: Html) -> Html {
+    Kbd(attributes, Box::new(children))
+}
+
+/// Create a li element.
+pub fn li(children: Html) -> Html {
+    Li(Box::new(children))
+}
+
 /// Create a mark element.
 pub fn mark(children: Html) -> Html {
     Mark(Box::new(children))
 }
 
+/// Get the value of the named attribute `name`.
+fn named<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Attribute::Named(ref attribute_name, ref value) if attribute_name == name => Some(value.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
+/// Get the first positional attribute after the style, like the language in `[source,rust]`.
+fn positional(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Attribute::Positional(ref value) => Some(value.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
 /// Create a p element.
 pub fn p(children: Html) -> Html {
-    P(Box::new(children))
+    P(String::new(), Box::new(children))
+}
+
+/// Create a p element with attributes.
+pub fn p_a(attributes: String, children: Html) -> Html {
+    P(attributes, Box::new(children))
+}
+
+/// Create a pre element.
+pub fn pre(children: Html) -> Html {
+    Pre(String::new(), Box::new(children))
+}
+
+/// Create a pre element with attributes.
+pub fn pre_a(attributes: String, children: Html) -> Html {
+    Pre(attributes, Box::new(children))
+}
+
+/// Get the HTML attributes of the pre element of a verbatim block, whose lines are not wrapped with
+/// the `nowrap` option.
+fn pre_attributes(base: &str, attributes: &[Attribute]) -> String {
+    let mut classes = vec![];
+    if !base.is_empty() {
+        classes.push(base);
+    }
+    if has_option(attributes, "nowrap") {
+        classes.push("nowrap");
+    }
+    if classes.is_empty() {
+        return String::new();
+    }
+    attr! { class = classes.join(" ") }
+}
+
+/// Get the ID of a block with the text of the cross references to it: its `reftext`, or the title
+/// of a section.
+fn reference(node: &Node) -> Option<(String, String)> {
+    let attributes =
+        match *node {
+            Admonition(_, _, ref attributes) | DescriptionList(_, ref attributes) | Example(_, ref attributes) |
+                Listing(_, ref attributes) | Literal(_, ref attributes) | Paragraph(_, ref attributes) |
+                UnorderedList(_, ref attributes) => attributes,
+            Section(ref section) if section.id.is_empty() => return None,
+            Section(ref section) => {
+                let text = named(&section.attributes, "reftext")
+                    .map_or_else(|| section.title.plain(), str::to_string);
+                return Some((section.id.clone(), text));
+            },
+            Node::Table(ref table) => &table.attributes,
+            _ => return None,
+        };
+    Some((find_id_attribute(attributes)?, named(attributes, "reftext")?.to_string()))
+}
+
+/// The cross references found in a document, collected before generating it so that a cross
+/// reference can refer to a block which follows it.
+/// The captioned blocks are counted like when generating them, with the document `attributes`,
+/// for the cross references to them.
+struct References {
+    attributes: AttributeRegistry,
+    captions: CaptionRegistry,
+    references: HashMap<String, String>,
+}
+
+impl References {
+    fn new(attributes: AttributeRegistry) -> Self {
+        References {
+            attributes,
+            captions: CaptionRegistry::new(),
+            references: HashMap::new(),
+        }
+    }
+}
+
+impl Visitor for References {
+    fn visit_node(&mut self, node: &Node) {
+        match *node {
+            AttributeEntry(ref name, Some(ref value)) => self.attributes.set(name, value),
+            AttributeEntry(ref name, None) => self.attributes.unset(name),
+            _ => (),
+        }
+        if let Some((kind, attributes)) = captioned_block(node) {
+            if let Some(label) = self.attributes.get(&format!("{}-caption", kind)).map(str::to_string) {
+                self.captions.caption(kind, &label);
+                if let Some((id, text)) = caption_reference(attributes, &label, self.captions.number(kind)) {
+                    self.references.insert(id, text);
+                }
+            }
+        }
+        if let Some((id, text)) = reference(node) {
+            self.references.insert(id, text);
+        }
+        walk_node(self, node);
+    }
 }
 
 /// Create a span element.
@@ -209,27 +1843,154 @@ pub fn span_a(attributes: String, children: Html) -> Html {
     Span(attributes, Box::new(children))
 }
 
-fn tag<W: Write>(name: &str, children: &Html, writer: &mut W) -> Result<()> {
-    write!(writer, "<{}>", name)?;
-    children.write(writer)?;
-    write!(writer, "</{}>", name)?;
-    Ok(())
+/// Get the style of a block, its first positional attribute.
+fn style(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Style(ref style) => Some(style.as_str()),
+                _ => None,
+            })
+        .next()
 }
 
-fn tag_a<W: Write>(name: &str, attributes: &str, children: &Html, writer: &mut W) -> Result<()> {
-    write!(writer, "<{} {}>", name, attributes)?;
-    children.write(writer)?;
-    write!(writer, "</{}>", name)?;
-    Ok(())
+/// Create a summary element with attributes.
+pub fn summary_a(attributes: String, children: Html) -> Html {
+    Summary(attributes, Box::new(children))
+}
+
+/// Create a table element with attributes.
+pub fn table_a(attributes: String, children: Html) -> Html {
+    Html::Table(attributes, Box::new(children))
+}
+
+/// Create a tbody element.
+pub fn tbody(children: Html) -> Html {
+    Tbody(Box::new(children))
+}
+
+/// Create a td element with attributes.
+pub fn td_a(attributes: String, children: Html) -> Html {
+    Td(attributes, Box::new(children))
 }
 
-fn tag_a_without_child<W: Write>(name: &str, attributes: &str, writer: &mut W) -> Result<()> {
+/// Create a tfoot element.
+pub fn tfoot(children: Html) -> Html {
+    Tfoot(Box::new(children))
+}
+
+/// Create a th element with attributes.
+pub fn th_a(attributes: String, children: Html) -> Html {
+    Th(attributes, Box::new(children))
+}
+
+/// Create a thead element.
+pub fn thead(children: Html) -> Html {
+    Thead(Box::new(children))
+}
+
+/// Get the side of the sidebar where the table of contents is placed with `:toc: left` or
+/// `:toc: right`.
+fn toc_sidebar(attributes: &AttributeRegistry) -> Option<&str> {
+    attributes.get("toc").filter(|&placement| placement == "left" || placement == "right")
+}
+
+/// Create a tr element.
+pub fn tr(children: Html) -> Html {
+    Tr(Box::new(children))
+}
+
+/// Create a ul element.
+pub fn ul(children: Html) -> Html {
+    Ul(String::new(), Box::new(children))
+}
+
+/// Create a ul element with attributes.
+pub fn ul_a(attributes: String, children: Html) -> Html {
+    Ul(attributes, Box::new(children))
+}
+
+/// Create a video element with attributes.
+pub fn video_a(attributes: String, children: Html) -> Html {
+    Video(attributes, Box::new(children))
+}
+
+/// Create a void element with attributes, like an input element.
+pub fn void_a(name: &'static str, attributes: String) -> Html {
+    Void(name, attributes)
+}
+
+/// Check whether the void elements are self-closed, like `<br/>`: they are unless the `htmlsyntax`
+/// document attribute is `html`.
+fn xhtml<G: HtmlGen>(gen: &mut G) -> bool {
+    gen.attributes().get("htmlsyntax") != Some("html")
+}
+
+/// Write a heading element of `level`, like `<h2>`.
+fn heading<W: fmt::Write>(level: u8, attributes: &str, children: &Html, xhtml: bool, writer: &mut W) -> fmt::Result {
+    write!(writer, "<h{}", level)?;
+    if !attributes.is_empty() {
+        write!(writer, " {}", attributes)?;
+    }
+    write_text(">", writer)?;
+    children.format_with_syntax(writer, xhtml)?;
+    write!(writer, "</h{}>", level)
+}
+
+fn tag<W: fmt::Write>(name: &str, children: &Html, xhtml: bool, writer: &mut W) -> fmt::Result {
+    tag_a(name, "", children, xhtml, writer)
+}
+
+/// Write an element with its children, or without them nor a closing tag for a void element.
+fn tag_a<W: fmt::Write>(name: &str, attributes: &str, children: &Html, xhtml: bool, writer: &mut W) -> fmt::Result {
+    if VOID_ELEMENTS.contains(&name) {
+        return void(name, attributes, xhtml, writer);
+    }
+    if attributes.is_empty() {
+        write!(writer, "<{}>", name)?;
+    }
+    else {
+        write!(writer, "<{} {}>", name, attributes)?;
+    }
+    children.format_with_syntax(writer, xhtml)?;
+    write!(writer, "</{}>", name)
+}
+
+fn tag_a_without_child<W: fmt::Write>(name: &str, attributes: &str, writer: &mut W) -> fmt::Result {
     write!(writer, "<{} {}>", name, attributes)?;
-    write!(writer, "</{}>", name)?;
-    Ok(())
+    write!(writer, "</{}>", name)
 }
 
-fn write_text<W: Write>(text: &str, writer: &mut W) -> Result<()> {
-    write!(writer, "{}", text)?;
-    Ok(())
+/// Write a void element, which is self-closed when `xhtml` is true, like `<br/>`.
+fn void<W: fmt::Write>(name: &str, attributes: &str, xhtml: bool, writer: &mut W) -> fmt::Result {
+    write!(writer, "<{}", name)?;
+    if !attributes.is_empty() {
+        write!(writer, " {}", attributes)?;
+    }
+    write_text(if xhtml { "/>" } else { ">" }, writer)
+}
+
+/// Write the `text` with its special characters escaped, including the double quotes when `quotes`
+/// is true, like for the value of an attribute.
+fn write_escaped<W: fmt::Write>(text: &str, quotes: bool, writer: &mut W) -> fmt::Result {
+    let mut start = 0;
+    for (index, char) in text.char_indices() {
+        let escaped =
+            match char {
+                '&' => "&amp;",
+                '<' => "&lt;",
+                '>' => "&gt;",
+                '"' if quotes => "&quot;",
+                '\u{a0}' => "&#160;",
+                _ => continue,
+            };
+        writer.write_str(&text[start..index])?;
+        writer.write_str(escaped)?;
+        start = index + char.len_utf8();
+    }
+    writer.write_str(&text[start..])
+}
+
+fn write_text<W: fmt::Write>(text: &str, writer: &mut W) -> fmt::Result {
+    writer.write_str(text)
 }
@@ -19,4 +19,31 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+//! The backends generating the output of a document, like HTML.
+
 pub mod html;
+pub mod text;
+
+use std::io::Write;
+
+use attributes::AttributeRegistry;
+use error::Result;
+use ids::IdStyle;
+use node::Node;
+
+/// A backend writing the output of the nodes of a document, which `convert_with_backend()` uses.
+/// The HTML generators and the plain text generator are backends.
+pub trait Backend {
+    /// The document attributes which the document is parsed with, like `experimental`.
+    fn parser_attributes(&mut self) -> AttributeRegistry {
+        AttributeRegistry::new()
+    }
+
+    /// The style of the IDs which the parser generates for the sections without an explicit ID.
+    fn id_style(&self) -> IdStyle {
+        IdStyle::default()
+    }
+
+    /// Write the output for the whole document made of `nodes` in the `writer`.
+    fn write_document(&mut self, nodes: &[Node], writer: &mut dyn Write) -> Result<()>;
+}
@@ -0,0 +1,265 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Generate plain text from the asciidoctor nodes, for previews in a terminal.
+
+use std::io::Write;
+
+use error::Result;
+use gen::Backend;
+use node::{Attribute, Cell, CellContent, DescriptionItem, HAlign, Header, Item, ListItem, Node, Section, Tag, Text};
+use node::Node::*;
+
+/// The characters underlining the titles, from the document title to the sections of level 5.
+const UNDERLINES: [char; 6] = ['=', '-', '~', '^', '+', '+'];
+
+/// The plain text backend.
+#[derive(Default)]
+pub struct Generator;
+
+impl Backend for Generator {
+    fn write_document(&mut self, nodes: &[Node], mut writer: &mut dyn Write) -> Result<()> {
+        gen_document(nodes, &mut writer)
+    }
+}
+
+/// Write the plain text for the whole document made of `nodes` in the `writer`.
+pub fn gen_document<W: Write>(nodes: &[Node], writer: &mut W) -> Result<()> {
+    let text = blocks(nodes).join("\n");
+    if !text.is_empty() {
+        writeln!(writer, "{}", text)?;
+    }
+    Ok(())
+}
+
+/// Write the plain text for the specified `node` in the `writer`.
+pub fn gen<W: Write>(node: &Node, writer: &mut W) -> Result<()> {
+    for line in block(node) {
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Get the lines of a block.
+fn block(node: &Node) -> Vec<String> {
+    match *node {
+        Admonition(kind, ref blocks, _) => {
+            let mut lines = self::blocks(blocks.iter().map(|block| &block.node));
+            match lines.first_mut() {
+                Some(first) => first.insert_str(0, &format!("{}: ", kind.title())),
+                None => lines.push(format!("{}:", kind.title())),
+            }
+            lines
+        },
+        AttributeEntry(..) | PageBreak | PassthroughBlock { .. } | Toc(_) => vec![],
+        DescriptionList(ref items, _) => description_list(items),
+        Example(ref blocks, _) | Preamble(ref blocks) => self::blocks(blocks.iter().map(|block| &block.node)),
+        Node::Header(ref header) => self::header(header),
+        HorizontalRule => vec!["-".repeat(10)],
+        Image { ref attributes, ref target } => vec![format!("[{}]", image_alt(attributes).unwrap_or(target))],
+        Listing(ref content, _) | Literal(ref content, _) => indent(content.lines().map(str::to_string).collect(), 4),
+        Media { kind, ref target, .. } => vec![format!("[{}: {}]", kind.name(), target)],
+        Paragraph(ref text, _) => vec![self::text(text)],
+        Raw(ref content, _) => content.lines().map(str::to_string).collect(),
+        Node::Section(ref section) => self::section(section),
+        Stem { ref content, .. } => vec![content.clone()],
+        Node::Table(ref table) => self::table(&table.rows),
+        UnorderedList(ref items, _) => unordered_list(items),
+    }
+}
+
+/// Get the lines of some blocks, separated by empty lines.
+fn blocks<'a, I: IntoIterator<Item = &'a Node>>(nodes: I) -> Vec<String> {
+    let mut lines = vec![];
+    for node in nodes {
+        let block = block(node);
+        if block.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(block);
+    }
+    lines
+}
+
+/// Get the text of a table cell, on a single line.
+fn cell(cell: &Cell) -> String {
+    match cell.content {
+        CellContent::Blocks(ref blocks) => {
+            let lines = self::blocks(blocks.iter().map(|block| &block.node));
+            let lines: Vec<_> = lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+            lines.join(" ")
+        },
+        CellContent::Literal(ref source) => source.lines().map(str::trim).collect::<Vec<_>>().join(" "),
+        CellContent::Paragraphs(ref paragraphs) => paragraphs.iter().map(text).collect::<Vec<_>>().join(" "),
+    }
+}
+
+fn description_list(items: &[DescriptionItem]) -> Vec<String> {
+    let mut lines = vec![];
+    for item in items {
+        lines.push(text(&item.term));
+        let mut description = vec![];
+        if !item.description.items.is_empty() {
+            description.push(text(&item.description));
+        }
+        description.extend(blocks(item.blocks.iter().map(|block| &block.node)));
+        lines.extend(indent(description, 2));
+    }
+    lines
+}
+
+fn header(header: &Header) -> Vec<String> {
+    let mut lines = title(&text(&header.title), 0);
+    let authors: Vec<_> = header.authors.iter().map(|author| author.fullname()).collect();
+    if !authors.is_empty() {
+        lines.push(authors.join(", "));
+    }
+    let revision: Vec<_> = header.revnumber.iter().chain(header.revdate.iter()).cloned().collect();
+    if !revision.is_empty() {
+        lines.push(revision.join(", "));
+    }
+    lines
+}
+
+/// Get the alternate text of an image, like `Sunset` for `image::sunset.jpg[Sunset]`.
+fn image_alt(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Attribute::Named(ref name, ref value) if name == "alt" => Some(value.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
+/// Indent the non-empty lines by `width` spaces.
+fn indent(lines: Vec<String>, width: usize) -> Vec<String> {
+    lines.into_iter()
+        .map(|line|
+            if line.is_empty() {
+                line
+            }
+            else {
+                format!("{}{}", " ".repeat(width), line)
+            })
+        .collect()
+}
+
+fn section(section: &Section) -> Vec<String> {
+    let mut lines = title(&text(&section.title), section.level);
+    if !section.blocks.is_empty() {
+        lines.push(String::new());
+        lines.extend(blocks(section.blocks.iter().map(|block| &block.node)));
+    }
+    lines
+}
+
+/// Get the lines of a table, with its cells aligned in columns.
+fn table(rows: &[Vec<Cell>]) -> Vec<String> {
+    let rows: Vec<Vec<(String, HAlign)>> = rows.iter()
+        .map(|row| row.iter().map(|cell| (self::cell(cell), cell.halign)).collect())
+        .collect();
+    let mut widths: Vec<usize> = vec![];
+    for row in &rows {
+        for (index, (text, _)) in row.iter().enumerate() {
+            let width = text.chars().count();
+            if index < widths.len() {
+                widths[index] = widths[index].max(width);
+            }
+            else {
+                widths.push(width);
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<_> = row.iter().zip(&widths)
+                .map(|(&(ref text, halign), &width)|
+                    match halign {
+                        HAlign::Center => format!("{:^width$}", text, width = width),
+                        HAlign::Left => format!("{:<width$}", text, width = width),
+                        HAlign::Right => format!("{:>width$}", text, width = width),
+                    })
+                .collect();
+            cells.join(" | ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Get a text on a single line: bold text is shown between `*` and italic text between `_`.
+fn text(text: &Text) -> String {
+    let mut string = String::new();
+    for item in &text.items {
+        match *item {
+            Item::Button(ref label) => string.push_str(&format!("[{}]", label)),
+            Item::Kbd(ref keys) => string.push_str(&keys.join("+")),
+            Item::Link { ref target, ref text } =>
+                match *text {
+                    Some(ref text) => string.push_str(&format!("{} <{}>", text, target)),
+                    None => string.push_str(target),
+                },
+            Item::Icon { ref name, .. } => string.push_str(&format!("[{}]", name)),
+            Item::IndexTerm { ref terms, visible: true, .. } => string.push_str(&terms[0]),
+            Item::IndexTerm { .. } => (),
+            Item::Mark(ref text) | Item::Span { ref text, .. } => string.push_str(&self::text(text)),
+            Item::Menu(ref menu, ref items) => {
+                string.push_str(menu);
+                for item in items {
+                    string.push_str(" > ");
+                    string.push_str(item);
+                }
+            },
+            Item::SetAttribute(..) => (),
+            Item::NewLine | Item::Space => string.push(' '),
+            Item::Passthrough { ref content, .. } | Item::Stem { ref content, .. } => string.push_str(content),
+            Item::Tag(Tag::Bold, ref text, _) => string.push_str(&format!("*{}*", self::text(text))),
+            Item::Tag(Tag::Italic, ref text, _) => string.push_str(&format!("_{}_", self::text(text))),
+            Item::Tag(_, ref text, _) => string.push_str(&self::text(text)),
+            Item::Word(ref word) => string.push_str(word),
+            Item::Xref { ref id, ref text, .. } =>
+                match *text {
+                    Some(ref text) => string.push_str(text),
+                    None => string.push_str(&format!("[{}]", id)),
+                },
+        }
+    }
+    string
+}
+
+/// Get the lines of a title of `level`, underlined.
+fn title(title: &str, level: usize) -> Vec<String> {
+    let underline = UNDERLINES[level.min(UNDERLINES.len() - 1)];
+    vec![title.to_string(), underline.to_string().repeat(title.chars().count())]
+}
+
+fn unordered_list(items: &[ListItem]) -> Vec<String> {
+    let mut lines = vec![];
+    for item in items {
+        lines.push(format!("- {}", text(&item.text)));
+        for block in &item.blocks {
+            lines.extend(indent(self::block(&block.node), 2));
+        }
+    }
+    lines
+}
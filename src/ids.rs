@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! The IDs used in a document, to make the generated ones unique.
+//! The generated IDs only depend on the order of the document, so that converting the same
+//! document always gives the same IDs.
+
+use std::collections::HashSet;
+
+/// The style of the IDs generated for the sections from their title.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IdStyle {
+    /// The style of asciidoctor, like `_hello_world` for `Hello, World!`.
+    #[default]
+    Asciidoctor,
+    /// The style of GitHub, like `hello-world` for `Hello, World!`.
+    GitHub,
+    /// No generated IDs: the sections only have their explicit ID, if any.
+    None,
+}
+
+impl IdStyle {
+    /// Generate the ID of a section from its `title`, like `_section_title` for `Section Title`
+    /// in the asciidoctor style.
+    pub fn id(self, title: &str) -> Option<String> {
+        match self {
+            IdStyle::Asciidoctor => {
+                let mut id = "_".to_string();
+                for char in title.to_lowercase().chars() {
+                    if char.is_alphanumeric() {
+                        id.push(char);
+                    }
+                    else if !id.ends_with('_') {
+                        id.push('_');
+                    }
+                }
+                let length = id.trim_end_matches('_').len().max(1);
+                id.truncate(length);
+                Some(id)
+            },
+            IdStyle::GitHub => Some(title.to_lowercase().chars()
+                .filter_map(|char|
+                    if char.is_alphanumeric() || char == '-' || char == '_' {
+                        Some(char)
+                    }
+                    else if char.is_whitespace() {
+                        Some('-')
+                    }
+                    else {
+                        None
+                    })
+                .collect()),
+            IdStyle::None => None,
+        }
+    }
+}
+
+/// The IDs of the document, explicit or generated.
+#[derive(Debug)]
+pub struct IdRegistry {
+    ids: HashSet<String>,
+    /// The separator between a generated ID and the number making it unique.
+    separator: String,
+}
+
+impl Default for IdRegistry {
+    fn default() -> Self {
+        IdRegistry::with_separator("_")
+    }
+}
+
+impl IdRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        IdRegistry::default()
+    }
+
+    /// Create an empty registry whose generated IDs are made unique with the `separator` before
+    /// their number, like `_intro-2` for `-`.
+    pub fn with_separator(separator: &str) -> Self {
+        IdRegistry {
+            ids: HashSet::new(),
+            separator: separator.to_string(),
+        }
+    }
+
+    /// Check whether the ID is already used.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Register an explicit ID.
+    /// Return false if the ID was already used.
+    pub fn register(&mut self, id: &str) -> bool {
+        self.ids.insert(id.to_string())
+    }
+
+    /// Register a generated ID, made unique by appending a number to it if it is already used, like
+    /// `_intro_2`.
+    pub fn unique(&mut self, id: String) -> String {
+        let separator = self.separator.clone();
+        self.numbered(id, &separator, 2)
+    }
+
+    /// Register an ID generated in the `style`, made unique as this style does: like `unique()` for
+    /// the asciidoctor style and like `hello-world-1` for the second `hello-world` in the GitHub
+    /// style.
+    pub fn unique_in_style(&mut self, id: String, style: IdStyle) -> String {
+        match style {
+            IdStyle::GitHub => self.numbered(id, "-", 1),
+            IdStyle::Asciidoctor | IdStyle::None => self.unique(id),
+        }
+    }
+
+    /// Register `id`, or the first one of `id`, `separator` and a number from `first` which is not
+    /// used yet.
+    fn numbered(&mut self, id: String, separator: &str, first: usize) -> String {
+        let mut unique = id.clone();
+        let mut number = first;
+        while self.contains(&unique) {
+            unique = format!("{}{}{}", id, separator, number);
+            number += 1;
+        }
+        self.register(&unique);
+        unique
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Expand the include directives, like `include::chapter.adoc[]`, before parsing a document.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use error::{Error, Result};
+use parser::DEFAULT_MAX_DEPTH;
+use safe_mode::SafeMode;
+
+/// Resolve the target of an include directive to its content.
+/// This decouples the expansion of the includes from the file system.
+pub trait Resolver {
+    /// Get the content of the `target`, like `chapter.adoc`.
+    fn resolve(&self, target: &str) -> Result<String>;
+
+    /// Get the safe mode of the includes: with `SafeMode::Secure`, the include directives are
+    /// replaced by links to their target instead of being resolved.
+    fn safe_mode(&self) -> SafeMode {
+        SafeMode::Unsafe
+    }
+}
+
+/// A resolver reading the included files relative to a base directory.
+pub struct FileResolver {
+    base: PathBuf,
+    safe_mode: SafeMode,
+}
+
+impl FileResolver {
+    /// Create a resolver reading the files relative to the `base` directory.
+    pub fn new<P: Into<PathBuf>>(base: P) -> Self {
+        FileResolver {
+            base: base.into(),
+            safe_mode: SafeMode::default(),
+        }
+    }
+
+    /// Restrict the files which can be read with the `safe_mode`: from `SafeMode::Safe`, they must
+    /// be in the base directory.
+    pub fn safe_mode(mut self, safe_mode: SafeMode) -> Self {
+        self.safe_mode = safe_mode;
+        self
+    }
+}
+
+impl Resolver for FileResolver {
+    fn resolve(&self, target: &str) -> Result<String> {
+        let path = Path::new(target);
+        let outside = path.is_absolute() || path.components().any(|component| component == Component::ParentDir);
+        if outside && !self.safe_mode.allows_outside_files() {
+            return Err(Error::Msg(format!("the include target {} is outside the base directory", target)));
+        }
+        Ok(fs::read_to_string(self.base.join(path))?)
+    }
+
+    fn safe_mode(&self) -> SafeMode {
+        self.safe_mode
+    }
+}
+
+/// A resolver returning the content of in-memory files.
+#[derive(Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    /// Create a resolver from the pairs of names and contents of the files.
+    pub fn from_pairs(pairs: &[(&str, &str)]) -> Self {
+        MemoryResolver {
+            files: pairs.iter()
+                .map(|&(name, content)| (name.to_string(), content.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn resolve(&self, target: &str) -> Result<String> {
+        self.files.get(target)
+            .cloned()
+            .ok_or_else(|| Error::Msg(format!("cannot resolve the include target {}", target)))
+    }
+}
+
+/// Replace the include directives of the `source` by the content of their target, recursively.
+/// The content included in a verbatim block, like a listing block, is inserted as is instead,
+/// without expanding its own include directives.
+/// When the safe mode of the `resolver` does not allow the includes, the include directives are
+/// replaced by links to their target, like `link:chapter.adoc[]`.
+/// The includes are nested at most `DEFAULT_MAX_DEPTH` times, to stop on files including
+/// themselves.
+pub fn expand_includes<R: Resolver + ?Sized>(source: &str, resolver: &R) -> Result<String> {
+    expand_includes_with_max_depth(source, resolver, DEFAULT_MAX_DEPTH)
+}
+
+/// Like `expand_includes`, but with the includes nested at most `max_depth` times.
+pub fn expand_includes_with_max_depth<R: Resolver + ?Sized>(source: &str, resolver: &R, max_depth: usize)
+    -> Result<String>
+{
+    expand(source, resolver, 0, max_depth)
+}
+
+/// The delimiters of the verbatim blocks, whose included content is inserted as is.
+const VERBATIM_DELIMITERS: &[&str] = &["----", "++++"];
+
+fn expand<R: Resolver + ?Sized>(source: &str, resolver: &R, depth: usize, max_depth: usize) -> Result<String> {
+    let mut expanded = String::new();
+    let mut verbatim_delimiter = None;
+    for line in source.lines() {
+        match include_target(line) {
+            Some(target) if !resolver.safe_mode().allows_includes() => {
+                expanded.push_str(&format!("link:{}[]\n", target));
+            },
+            Some(target) => {
+                if depth >= max_depth {
+                    return Err(Error::MaxDepthExceeded(max_depth));
+                }
+                let content = resolver.resolve(target)?;
+                if verbatim_delimiter.is_some() {
+                    expanded.push_str(&content);
+                    if !content.is_empty() && !content.ends_with('\n') {
+                        expanded.push('\n');
+                    }
+                }
+                else {
+                    expanded.push_str(&expand(&content, resolver, depth + 1, max_depth)?);
+                }
+            },
+            None => {
+                if verbatim_delimiter == Some(line) {
+                    verbatim_delimiter = None;
+                }
+                else if verbatim_delimiter.is_none() && VERBATIM_DELIMITERS.contains(&line) {
+                    verbatim_delimiter = Some(line);
+                }
+                expanded.push_str(line);
+                expanded.push('\n');
+            },
+        }
+    }
+    Ok(expanded)
+}
+
+/// Get the target of an include directive, like `chapter.adoc` in `include::chapter.adoc[]`.
+fn include_target(line: &str) -> Option<&str> {
+    let target = line.strip_prefix("include::")?;
+    let bracket = target.find('[')?;
+    if !target.ends_with(']') || bracket == 0 {
+        return None;
+    }
+    Some(&target[..bracket])
+}
@@ -22,7 +22,10 @@
 //! Return the tokens from an asciidoctor text.
 
 use std::char;
-use std::io::Read;
+use std::str;
+use std::string::String;
+use std::string::ToString;
+use std::vec::Vec;
 
 use error::Error;
 use error::Result;
@@ -30,6 +33,29 @@ use position::Pos;
 use token::Token;
 use token::Token::*;
 
+/// The source of the bytes of a document.
+#[cfg(feature = "std")]
+pub use std::io::Read;
+
+/// The source of the bytes of a document, implemented for byte slices since `std::io` is not
+/// available.
+#[cfg(not(feature = "std"))]
+pub trait Read {
+    /// Read some bytes into `buffer` and return how many were read, 0 at the end of the input.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize>;
+}
+
+#[cfg(not(feature = "std"))]
+impl Read for &[u8] {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let size = buffer.len().min(self.len());
+        let (read, rest) = self.split_at(size);
+        buffer[..size].copy_from_slice(read);
+        *self = rest;
+        Ok(size)
+    }
+}
+
 macro_rules! lex {
     ($func_name:ident, $char:expr, $token:ident) => {
         fn $func_name(&mut self) -> Result<Token> {
@@ -56,16 +82,27 @@ macro_rules! lex1_or_2 {
 const BUFFER_SIZE: usize = 4096;
 
 struct NextToken {
+    end: Pos,
     previous_pos: Pos,
     token: Token,
 }
 
 pub struct Lexer<R: Read> {
-    buffer: [u8; BUFFER_SIZE],
+    /// Whether only spaces were read since the start of the current line, where a `//` starts a
+    /// comment.
+    at_line_start: bool,
+    buffer: Vec<u8>,
     buffer_index: usize,
     buffer_size: usize,
     column: usize,
+    /// The position of the last character of the last token returned, other than a newline.
+    end: Pos,
     line: usize,
+    /// Whether the invalid UTF-8 sequences are replaced by `U+FFFD` instead of causing an error.
+    lossy: bool,
+    /// Whether the last line read so far does not end with a newline: a newline is then lexed at
+    /// the end of the file, so that the last line ends like the others.
+    missing_newline: bool,
     next_token: Option<NextToken>,
     reader: R,
 }
@@ -74,20 +111,57 @@ impl<R: Read> Lexer<R> {
     /// Create a new parser from a `Reader`.
     /// This is an iterator over the tokens.
     pub fn new(reader: R) -> Self {
+        Lexer::starting_at(reader, Pos::new(1, 1))
+    }
+
+    /// Create a new lexer from a `Reader` which starts at the position `pos` of a document.
+    /// This allows to re-tokenize a document from a line boundary after it was edited: the positions
+    /// reported by the lexer are then relative to the start of the whole document.
+    pub fn starting_at(reader: R, pos: Pos) -> Self {
         Lexer {
-            buffer: [0; BUFFER_SIZE],
+            at_line_start: pos.column == 1,
+            buffer: vec![0; BUFFER_SIZE],
             buffer_index: BUFFER_SIZE,
             buffer_size: 0,
-            column: 1,
-            line: 1,
+            column: pos.column,
+            end: pos,
+            line: pos.line,
+            lossy: false,
+            missing_newline: false,
             next_token: None,
             reader,
         }
     }
 
+    /// Replace the invalid UTF-8 sequences of the document by `U+FFFD` when `lossy` is true,
+    /// instead of returning an `Error::InvalidUtf8`.
+    pub fn lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// Take back the reader, with the bytes which were read from it but not tokenized yet, which
+    /// precede the rest of its bytes.
+    /// The bytes of a token which was peeked, but not returned, are not part of them, and they end
+    /// with a newline when the lexer reached the end of a document which does not end with one.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        let remaining = self.buffer[self.buffer_index.min(self.buffer_size)..self.buffer_size].to_vec();
+        (self.reader, remaining)
+    }
+
+    /// Reset the position of the next character to `pos`, like the start of the document, so that
+    /// the positions reported afterwards are relative to it.
+    pub fn reset_position(&mut self, pos: Pos) {
+        self.at_line_start = pos.column == 1;
+        self.column = pos.column;
+        self.end = pos;
+        self.line = pos.line;
+    }
+
     /// Advance the internal position cursor.
     fn advance(&mut self, actual: u8) {
         self.buffer_index += 1;
+        self.at_line_start = actual == b'\n' || (self.at_line_start && actual == b' ');
         if actual == b'\n' {
             self.line += 1;
             self.column = 1;
@@ -118,6 +192,7 @@ impl<R: Read> Lexer<R> {
     lex1_or_2!(backquote, b'`', Backquote, DoubleBackquote);
     lex!(caret, b'^', Caret);
     lex!(close_square_bracket, b']', CloseSquareBracket);
+    lex!(colon, b':', Colon);
     lex!(newline, b'\n', NewLine);
     lex!(number_sign, b'#', NumberSign);
     lex!(open_square_bracket, b'[', OpenSquareBracket);
@@ -126,10 +201,9 @@ impl<R: Read> Lexer<R> {
     lex!(tilde, b'~', Tilde);
     lex1_or_2!(underscore, b'_', Underscore, DoubleUnderscore);
 
-    /// Parse (and ignore) a comment.
+    /// Parse (and ignore) a comment, whose first `/` was just eaten.
     fn comment(&mut self) -> Result<()> {
         self.eat(b'/')?;
-        self.eat(b'/')?;
 
         // Try to parse a multiline comment.
         if self.current_char()? == b'/' {
@@ -137,7 +211,9 @@ impl<R: Read> Lexer<R> {
             self.eat(b'/')?;
 
             let comment_delim = b"////";
-            while &self.buffer[self.buffer_index..self.buffer_index + comment_delim.len()] != comment_delim {
+            while self.buffer[..self.buffer_size].get(self.buffer_index..self.buffer_index + comment_delim.len()) !=
+                Some(&comment_delim[..])
+            {
                 self.advance_to_eol()?;
                 self.advance_while(|c| c == b'\n')?;
             }
@@ -153,6 +229,27 @@ impl<R: Read> Lexer<R> {
         Ok(())
     }
 
+    /// Get the length of the content of a constrained passthrough, like `+*text*+`, whose opening
+    /// `+` was just eaten, when it is closed in the rest of its line: its content does not start or
+    /// end with a space and its closing `+` is not followed by a letter or a digit.
+    fn constrained_passthrough_len(&mut self) -> Result<Option<usize>> {
+        self.fill_line()?;
+        let rest = &self.buffer[self.buffer_index.min(self.buffer_size)..self.buffer_size];
+        if rest.first().is_none_or(|&char| char == b' ' || char == b'\n') {
+            return Ok(None);
+        }
+        let is_closing = |index: usize| rest[index - 1] != b' ' &&
+            rest.get(index + 1).is_none_or(|&next| !next.is_ascii_alphanumeric() && next != b'_');
+        for (index, &char) in rest.iter().enumerate().skip(1) {
+            match char {
+                b'\n' => return Ok(None),
+                b'+' if is_closing(index) => return Ok(Some(index)),
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+
     /// Get the current character (filling the buffer if needed).
     fn current_char(&mut self) -> Result<u8> {
         self.read_if_needed()?;
@@ -176,13 +273,97 @@ impl<R: Read> Lexer<R> {
         }
     }
 
+    /// Read from the reader until the buffer holds the rest of the current line, with its newline,
+    /// growing the buffer for a long line, so that it can be looked at before it is tokenized.
+    fn fill_line(&mut self) -> Result<()> {
+        let start = self.buffer_index.min(self.buffer_size);
+        if self.buffer[start..self.buffer_size].contains(&b'\n') {
+            return Ok(());
+        }
+        self.buffer.copy_within(start..self.buffer_size, 0);
+        self.buffer_size -= start;
+        self.buffer_index = 0;
+        loop {
+            if self.buffer_size == self.buffer.len() {
+                let size = self.buffer.len() * 2;
+                self.buffer.resize(size, 0);
+            }
+            let size = self.reader.read(&mut self.buffer[self.buffer_size..])?;
+            if size == 0 {
+                // Like read_if_needed(), end the last line of a document without a final newline.
+                if self.missing_newline {
+                    if self.buffer_size == self.buffer.len() {
+                        self.buffer.push(0);
+                    }
+                    self.buffer[self.buffer_size] = b'\n';
+                    self.buffer_size += 1;
+                    self.missing_newline = false;
+                }
+                return Ok(());
+            }
+            let read = &self.buffer[self.buffer_size..self.buffer_size + size];
+            self.missing_newline = read.last() != Some(&b'\n');
+            let has_newline = read.contains(&b'\n');
+            self.buffer_size += size;
+            if has_newline {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read the rest of the current line as is, without tokenizing it.
+    /// The newline is consumed, but not returned.
+    pub fn line(&mut self) -> Result<Vec<u8>> {
+        if self.next_token.is_some() {
+            return Err(Error::Msg("bug in the parser, cannot read a line after peeking a token".to_string()));
+        }
+        let start = Pos::new(self.line, self.column);
+        let mut line = vec![];
+        loop {
+            match self.current_char() {
+                Ok(b'\n') => {
+                    if !line.is_empty() {
+                        self.end = self.last_char_pos();
+                    }
+                    self.advance(b'\n');
+                    break;
+                },
+                Ok(actual) => {
+                    line.push(actual);
+                    self.advance(actual);
+                },
+                Err(Error::Eof) if !line.is_empty() => break,
+                Err(error) => return Err(error),
+            }
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        self.utf8(line, start)
+    }
+
+    /// Peek at the next character as is, without tokenizing it.
+    pub fn peek_char(&mut self) -> Result<u8> {
+        if self.next_token.is_some() {
+            return Err(Error::Msg("bug in the parser, cannot read a character after peeking a token".to_string()));
+        }
+        self.current_char()
+    }
+
+    /// Peek at the character following the token which was peeked, as is.
+    pub fn peek_char_after_token(&mut self) -> Result<u8> {
+        self.current_char()
+    }
+
     /// Peek to get the next token. This token will be returned by the next call to token().
     pub fn peek(&mut self) -> Result<&Token> {
         if self.next_token.is_none() {
             let previous_pos = self.pos();
+            let token = self.lex()?;
             self.next_token = Some(NextToken {
-                token: self.token()?,
+                end: self.last_char_pos(),
                 previous_pos,
+                token,
             });
         }
         // The next_token attribute is assigned a Some value if it is None, so unwrap() always
@@ -204,34 +385,101 @@ impl<R: Read> Lexer<R> {
     fn read_if_needed(&mut self) -> Result<()> {
         if self.buffer_index >= self.buffer_size {
             self.buffer_size = self.reader.read(&mut self.buffer)?;
+            self.buffer_index = 0;
             if self.buffer_size == 0 {
-                return Err(Error::Eof);
+                if !self.missing_newline {
+                    return Err(Error::Eof);
+                }
+                self.buffer[0] = b'\n';
+                self.buffer_size = 1;
             }
-            self.buffer_index = 0;
+            self.missing_newline = self.buffer[self.buffer_size - 1] != b'\n';
         }
         Ok(())
     }
 
+    /// Get the position of the last character of the last token returned, other than a newline:
+    /// this is where the node made of the tokens returned so far ends.
+    pub fn end_pos(&self) -> Pos {
+        self.end
+    }
+
+    /// Get the position of the character preceding the current one, on the current line.
+    fn last_char_pos(&self) -> Pos {
+        Pos::new(self.line, self.column.saturating_sub(1))
+    }
+
     /// Get the next token from the file.
     pub fn token(&mut self) -> Result<Token> {
-        if let Some(token) = self.next_token.take() {
-            return Ok(token.token);
+        let (token, end) =
+            match self.next_token.take() {
+                Some(next_token) => (next_token.token, next_token.end),
+                None => {
+                    let token = self.lex()?;
+                    (token, self.last_char_pos())
+                },
+            };
+        if token != NewLine {
+            self.end = end;
         }
+        Ok(token)
+    }
+
+    /// Get the next token, or an `Error` token with its first byte when it cannot be lexed, for the
+    /// `RecoveringLexer`: only this byte is then eaten, and the lexing continues from the next one.
+    fn token_or_error_byte(&mut self) -> Result<Token> {
+        // The tokens which cannot be lexed end on their line, so the line holds their bytes to go
+        // back to.
+        self.fill_line()?;
+        let (at_line_start, buffer_index) = (self.at_line_start, self.buffer_index);
+        let (column, line) = (self.column, self.line);
+        match self.token() {
+            Err(Error::UnexpectedChar { .. }) => {
+                self.at_line_start = at_line_start;
+                self.buffer_index = buffer_index;
+                self.column = column;
+                self.line = line;
+                let byte = self.current_char()?;
+                self.advance(byte);
+                Ok(Token::Error(byte))
+            },
+            result => result,
+        }
+    }
+
+    /// Lex the next token, whose content is valid UTF-8.
+    fn lex(&mut self) -> Result<Token> {
         self.read_if_needed()?;
+        let start = Pos::new(self.line, self.column);
+        // The content of the cross references and passthroughs starts after their delimiter.
+        let constrained_content_start = Pos::new(self.line, self.column + 1);
+        let content_start = Pos::new(self.line, self.column + 2);
+        let raw_content_start = Pos::new(self.line, self.column + 3);
+        match self.lex_token()? {
+            ConstrainedPassthrough(content) =>
+                Ok(ConstrainedPassthrough(self.utf8(content, constrained_content_start)?)),
+            Passthrough(content) => Ok(Passthrough(self.utf8(content, content_start)?)),
+            RawPassthrough(content) => Ok(RawPassthrough(self.utf8(content, raw_content_start)?)),
+            Word(word) => Ok(Word(self.utf8(word, start)?)),
+            Xref(content) => Ok(Xref(self.utf8(content, content_start)?)),
+            token => Ok(token),
+        }
+    }
+
+    /// Lex the next token from the file.
+    fn lex_token(&mut self) -> Result<Token> {
         let actual = self.current_char()?;
         match actual {
-            b'/' => {
-                self.comment()?;
-                self.token()
-            },
-            b'<' => self.triple_lt(),
+            b'/' => self.slash(),
+            b'<' => self.lt(),
             b'\'' => self.triple_apos(),
             b'\n' => self.newline(),
             b'\r' => {
                 self.advance(actual);
-                self.token()
+                self.lex()
             },
             b'#' => self.number_sign(),
+            b'+' => self.plus(),
             b' ' => self.space(),
             b'[' => self.open_square_bracket(),
             b']' => self.close_square_bracket(),
@@ -240,10 +488,68 @@ impl<R: Read> Lexer<R> {
             b'`' => self.backquote(),
             b'^' => self.caret(),
             b'~' => self.tilde(),
+            b':' => self.colon(),
             _ => self.word(),
         }
     }
 
+    /// Parse an inline passthrough, like `++text++` or `+text+`, or a raw one, like `+++<b>raw</b>+++`.
+    /// Other `+`, like in `++++`, start a word, as does a passthrough which is not closed on its line.
+    fn plus(&mut self) -> Result<Token> {
+        let mut word = vec![];
+        while self.current_char()? == b'+' {
+            self.advance(b'+');
+            word.push(b'+');
+        }
+        if word.len() == 1 {
+            if let Some(len) = self.constrained_passthrough_len()? {
+                let content = self.buffer[self.buffer_index..self.buffer_index + len].to_vec();
+                for &actual in &content {
+                    self.advance(actual);
+                }
+                self.advance(b'+');
+                return Ok(ConstrainedPassthrough(content));
+            }
+        }
+        if word.len() == 2 || word.len() == 3 {
+            if let Some(len) = self.unconstrained_passthrough_len(&word)? {
+                let content = self.buffer[self.buffer_index..self.buffer_index + len].to_vec();
+                for &actual in content.iter().chain(&word) {
+                    self.advance(actual);
+                }
+                if word.len() == 3 {
+                    return Ok(RawPassthrough(content));
+                }
+                return Ok(Passthrough(content));
+            }
+        }
+        if is_word_char(self.current_char()?) {
+            if let Word(mut rest) = self.word()? {
+                word.append(&mut rest);
+            }
+        }
+        Ok(Word(word))
+    }
+
+    /// Parse a comment starting with `//` at the start of a line, after optional spaces, and lex the
+    /// token following it.
+    /// Elsewhere, like in the URL `http://example.com`, a `/` starts a word.
+    fn slash(&mut self) -> Result<Token> {
+        let at_line_start = self.at_line_start;
+        self.eat(b'/')?;
+        if at_line_start && self.current_char()? == b'/' {
+            self.comment()?;
+            return self.lex();
+        }
+        let mut word = b"/".to_vec();
+        if is_word_char(self.current_char()?) {
+            if let Word(mut rest) = self.word()? {
+                word.append(&mut rest);
+            }
+        }
+        Ok(Word(word))
+    }
+
     /// Parse three '.
     fn triple_apos(&mut self) -> Result<Token> {
         self.eat(b'\'')?;
@@ -252,23 +558,132 @@ impl<R: Read> Lexer<R> {
         Ok(TripleApos)
     }
 
-    /// Parse three <.
-    fn triple_lt(&mut self) -> Result<Token> {
-        self.eat(b'<')?;
+    /// Get the length of the content of an unconstrained passthrough, like `++text++`, whose opening
+    /// `delimiter` was just eaten, when it is closed in the rest of its line.
+    fn unconstrained_passthrough_len(&mut self, delimiter: &[u8]) -> Result<Option<usize>> {
+        self.fill_line()?;
+        let rest = &self.buffer[self.buffer_index.min(self.buffer_size)..self.buffer_size];
+        let line = &rest[..rest.iter().position(|&char| char == b'\n').unwrap_or(rest.len())];
+        Ok(line.windows(delimiter.len()).position(|window| window == delimiter))
+    }
+
+    /// Check that the `bytes` starting at `start` are valid UTF-8, or replace their invalid
+    /// sequences in lossy mode.
+    fn utf8(&self, bytes: Vec<u8>, start: Pos) -> Result<Vec<u8>> {
+        match str::from_utf8(&bytes) {
+            Ok(_) => Ok(bytes),
+            Err(_) if self.lossy => Ok(String::from_utf8_lossy(&bytes).into_owned().into_bytes()),
+            Err(error) => Err(Error::InvalidUtf8(Pos::new(start.line, start.column + error.valid_up_to()))),
+        }
+    }
+
+    /// Parse three <, or a cross reference like `<<id,text>>`.
+    /// A `<` starting neither of them is a word, as is a `<<` which is not closed on its line.
+    fn lt(&mut self) -> Result<Token> {
         self.eat(b'<')?;
+        if self.current_char()? != b'<' {
+            return Ok(Word(b"<".to_vec()));
+        }
         self.eat(b'<')?;
-        Ok(TripleLt)
+        if self.current_char()? == b'<' {
+            self.eat(b'<')?;
+            return Ok(TripleLt);
+        }
+        let mut content = vec![];
+        loop {
+            match self.current_char()? {
+                b'\n' => {
+                    let mut word = b"<<".to_vec();
+                    word.append(&mut content);
+                    return Ok(Word(word));
+                },
+                b'>' if content.last() == Some(&b'>') => {
+                    self.advance(b'>');
+                    content.pop();
+                    return Ok(Xref(content));
+                },
+                actual => {
+                    content.push(actual);
+                    self.advance(actual);
+                },
+            }
+        }
     }
 
     /// Parse a word.
     fn word(&mut self) -> Result<Token> {
-        let start_index = self.buffer_index;
-        self.advance_while(|c| !b" *_`#[]^~:\n\r\t".contains(&c))?;
-        if self.buffer_index == start_index {
+        // The bytes are copied as they are read, since the word can span two reads of the buffer.
+        let mut word = vec![];
+        loop {
+            let actual = self.current_char()?;
+            if !is_word_char(actual) {
+                break;
+            }
+            word.push(actual);
+            self.advance(actual);
+        }
+        if word.is_empty() {
             return Err(Error::Msg(format!("bug in the lexer, next character `{}` is not part of a word token",
                   char::from_u32(self.current_char()? as u32)
                       .ok_or("byte is not a character")?)));
         }
-        Ok(Word(self.buffer[start_index..self.buffer_index].to_vec()))
+        Ok(Word(word))
+    }
+}
+
+impl<'a> Lexer<&'a [u8]> {
+    /// Create a new lexer over the bytes of a whole document, which are all in its buffer at once
+    /// instead of being read in chunks: the tokens never span two reads.
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        let mut lexer = Lexer::new(&[][..]);
+        lexer.buffer = bytes.to_vec();
+        lexer.buffer_index = 0;
+        lexer.buffer_size = bytes.len();
+        lexer.missing_newline = bytes.last().is_some_and(|&byte| byte != b'\n');
+        lexer
+    }
+}
+
+/// Check whether a character can be part of a word token.
+fn is_word_char(char: u8) -> bool {
+    !b" *_`#[]^~:\n\r\t".contains(&char)
+}
+
+/// An iterator over the tokens of a lexer which does not stop at a token which cannot be lexed,
+/// like a lone `'`: it returns a `Token::Error` with the first byte of this token instead, and
+/// continues lexing from the byte following it, so that `''x` gives two errors.
+/// The other errors, like the invalid UTF-8 or the I/O errors, are returned once and end the
+/// iteration.
+pub struct RecoveringLexer<R: Read> {
+    /// Whether an error which cannot be recovered from was returned.
+    done: bool,
+    lexer: Lexer<R>,
+}
+
+impl<R: Read> RecoveringLexer<R> {
+    /// Create an iterator over the tokens of the `lexer`.
+    pub fn new(lexer: Lexer<R>) -> Self {
+        RecoveringLexer {
+            done: false,
+            lexer,
+        }
+    }
+}
+
+impl<R: Read> Iterator for RecoveringLexer<R> {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.lexer.token_or_error_byte() {
+            Ok(token) => Some(Ok(token)),
+            Err(Error::Eof) => None,
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            },
+        }
     }
 }
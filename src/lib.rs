@@ -20,25 +20,192 @@
  */
 
 //! Crate to parse asciidoctor and convert it to HTML.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only provides the lexer, which
+//! reads from byte slices.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+/// The parts of the standard library used by the lexer, taken from `core` and `alloc` when the
+/// `std` feature is disabled.
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use alloc::{string, vec};
+    pub use core::{char, fmt, result, str};
+}
+
+#[cfg(feature = "std")]
+mod attributes;
+#[cfg(feature = "std")]
+mod captions;
+#[cfg(feature = "std")]
+mod diagnostic;
 mod error;
+#[cfg(feature = "std")]
 mod gen;
+#[cfg(feature = "std")]
+mod ids;
+#[cfg(feature = "std")]
+mod include;
 mod lexer;
+#[cfg(feature = "std")]
 mod node;
+#[cfg(feature = "std")]
 mod parser;
 mod position;
+#[cfg(feature = "std")]
+mod safe_mode;
+#[cfg(feature = "std")]
+mod table;
 mod token;
+#[cfg(feature = "std")]
+mod visitor;
 
-//use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io::{BufRead, Write};
 
+#[cfg(feature = "std")]
+pub use attributes::AttributeRegistry;
+#[cfg(feature = "std")]
+pub use captions::CaptionRegistry;
+#[cfg(feature = "std")]
+pub use diagnostic::{Diagnostic, Level, warnings_as_json};
 pub use error::{Error, Result};
-pub use gen::html;
-pub use lexer::Lexer;
-pub use node::Node;
-pub use parser::Parser;
+#[cfg(feature = "std")]
+pub use gen::{Backend, html, text};
+#[cfg(feature = "std")]
+pub use ids::{IdRegistry, IdStyle};
+#[cfg(feature = "std")]
+pub use include::{FileResolver, MemoryResolver, Resolver, expand_includes, expand_includes_with_max_depth};
+pub use lexer::{Lexer, Read, RecoveringLexer};
+#[cfg(feature = "std")]
+pub use node::{Attribute, Author, Block, Cell, CellStyle, ColumnSpec, DescriptionItem, HAlign, Header, MediaKind,
+    Node, Section, Table, VAlign};
+#[cfg(feature = "std")]
+pub use parser::{DEFAULT_MAX_DEPTH, Parser};
+pub use position::{Pos, Span};
+#[cfg(feature = "std")]
+pub use safe_mode::SafeMode;
 pub use token::Token;
+#[cfg(feature = "std")]
+pub use visitor::{Visitor, walk_node};
 
-/*
-/// Convert the data read from the `reader` and write the output into the `writer`.
-pub fn to_html<R: Read, W: Write>(reader: R, writer: W) {
-}*/
+/// Parse the whole document read from the `reader`.
+#[cfg(feature = "std")]
+pub fn parse<R: BufRead>(reader: R) -> Result<Vec<Node>> {
+    nodes(Parser::new(Lexer::new(reader)))
+}
+
+/// Parse as much of the document read from the `reader` as possible, like for the diagnostics of
+/// an editor: after an error, the parsing continues at the next block.
+/// This returns the blocks which were parsed, with the errors in the order of the document. A block
+/// which the end of the document interrupts is an `Error::UnexpectedEof`.
+#[cfg(feature = "std")]
+pub fn parse_all<R: BufRead>(reader: R) -> (Vec<Node>, Vec<Error>) {
+    let mut parser = Parser::new(Lexer::new(reader));
+    let mut nodes = vec![];
+    let mut errors = vec![];
+    loop {
+        match parser.node() {
+            Ok(node) => nodes.push(node),
+            Err(Error::Eof) => break,
+            Err(error) => {
+                errors.push(error);
+                match parser.skip_to_next_block() {
+                    Ok(()) => (),
+                    Err(Error::Eof) => break,
+                    Err(error) => {
+                        errors.push(error);
+                        break;
+                    },
+                }
+            },
+        }
+    }
+    (nodes, errors)
+}
+
+/// Parse the whole document in `content`, like `parse()`, to process its nodes without converting
+/// it.
+#[cfg(feature = "std")]
+pub fn parse_str(content: &str) -> Result<Vec<Node>> {
+    parse(content.as_bytes())
+}
+
+/// Convert the document read from the `reader` and write the HTML output into the `writer`.
+/// The `attributes` are set before the document is converted; the attribute entries of the
+/// document can override them unless their name, like `sectnums@`, or their value ends with `@`.
+#[cfg(feature = "std")]
+pub fn convert<R: BufRead, W: Write>(reader: R, writer: &mut W, attributes: &HashMap<String, String>) -> Result<()> {
+    let mut generator = html::Generator::with_attributes(AttributeRegistry::with_attributes(attributes));
+    convert_with_backend(reader, writer, &mut generator)
+}
+
+/// Convert the inline `content`, like a single line, to HTML without wrapping it in a paragraph, as
+/// with the `inline` doctype: only the inline substitutions apply, like `*bold*` or `link:url[]`.
+#[cfg(feature = "std")]
+pub fn convert_inline(content: &str) -> Result<String> {
+    let mut buffer = vec![];
+    html::gen_inline(&mut html::Generator::new(), content, &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Convert only the top-level blocks of the document read from the `reader` which overlap the lines
+/// from `line_start` to `line_end`, inclusive, like the region edited in a live preview.
+/// The whole document is still parsed, so that the attribute entries preceding these blocks apply.
+#[cfg(feature = "std")]
+pub fn convert_range<R: BufRead, W: Write>(reader: R, writer: &mut W, attributes: &HashMap<String, String>,
+    line_start: usize, line_end: usize) -> Result<()>
+{
+    let mut backend = html::Generator::with_attributes(AttributeRegistry::with_attributes(attributes));
+    let mut parser = Parser::new(Lexer::new(reader)).attribute_registry(backend.parser_attributes());
+    let mut nodes = vec![];
+    loop {
+        match parser.node_with_span() {
+            Ok((node, span)) => {
+                let overlaps = span.start.line <= line_end && span.end.line >= line_start;
+                if overlaps || (span.start.line < line_start && matches!(node, Node::AttributeEntry(..))) {
+                    nodes.push(node);
+                }
+            },
+            Err(Error::Eof) => break,
+            Err(error) => return Err(error),
+        }
+    }
+    backend.write_document(&nodes, writer)
+}
+
+/// Convert the document read from the `reader` with the `backend`, like `text::Generator`, and
+/// write its output into the `writer`.
+#[cfg(feature = "std")]
+pub fn convert_with_backend<R, W, B>(reader: R, writer: &mut W, backend: &mut B) -> Result<()>
+    where R: BufRead,
+          W: Write,
+          B: Backend + ?Sized,
+{
+    let parser = Parser::new(Lexer::new(reader))
+        .attribute_registry(backend.parser_attributes())
+        .id_style(backend.id_style());
+    let nodes = nodes(parser)?;
+    backend.write_document(&nodes, writer)
+}
+
+/// Parse all the nodes of the `parser`.
+#[cfg(feature = "std")]
+fn nodes<R: BufRead>(mut parser: Parser<R>) -> Result<Vec<Node>> {
+    let mut nodes = vec![];
+    loop {
+        match parser.node() {
+            Ok(node) => nodes.push(node),
+            Err(Error::Eof) => break,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(nodes)
+}
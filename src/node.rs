@@ -19,21 +19,286 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+use std::mem;
+
+use position::Span;
+
 use self::Tag::*;
 
+/// The kind of an admonition, from the style of its block, like `[NOTE]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdmonitionKind {
+    Caution,
+    Important,
+    Note,
+    Tip,
+    Warning,
+}
+
+impl AdmonitionKind {
+    /// Get the kind from the style of a block, like `NOTE`.
+    pub fn from_style(style: &str) -> Option<AdmonitionKind> {
+        match style {
+            "CAUTION" => Some(AdmonitionKind::Caution),
+            "IMPORTANT" => Some(AdmonitionKind::Important),
+            "NOTE" => Some(AdmonitionKind::Note),
+            "TIP" => Some(AdmonitionKind::Tip),
+            "WARNING" => Some(AdmonitionKind::Warning),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the kind, as used in the HTML classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            AdmonitionKind::Caution => "caution",
+            AdmonitionKind::Important => "important",
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Warning => "warning",
+        }
+    }
+
+    /// Get the title of the kind, shown next to the content of the admonition.
+    pub fn title(self) -> &'static str {
+        match self {
+            AdmonitionKind::Caution => "Caution",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Warning => "Warning",
+        }
+    }
+}
+
 /// An attribute like a role or an ID.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Attribute {
     Id(String),
+    /// A named attribute, like `cols="1,2"`.
+    Named(String, String),
+    /// A positional attribute after the first one, like the language in `[source,rust]`.
+    Positional(String),
     Role(String),
+    /// The first positional attribute: the style of a block or the role of a text item.
+    Style(String),
+}
+
+/// An author from the author line of the document header.
+#[derive(Debug)]
+pub struct Author {
+    pub email: Option<String>,
+    pub firstname: String,
+    pub initials: String,
+    pub lastname: Option<String>,
+    pub middlename: Option<String>,
+}
+
+impl Author {
+    /// Get the full name of the author.
+    pub fn fullname(&self) -> String {
+        let mut fullname = self.firstname.clone();
+        for name in self.middlename.iter().chain(self.lastname.iter()) {
+            fullname.push(' ');
+            fullname.push_str(name);
+        }
+        fullname
+    }
+}
+
+/// A block nested in another node, like a block of a section, with its span in the document when
+/// the parser knows it.
+#[derive(Debug)]
+pub struct Block {
+    pub node: Node,
+    pub span: Option<Span>,
+}
+
+impl Block {
+    /// Create a new nested block from its node and its span.
+    pub fn new(node: Node, span: Option<Span>) -> Self {
+        Block {
+            node,
+            span,
+        }
+    }
+}
+
+/// A cell of a table, whose alignments and style are resolved from its column when the cell does
+/// not specify them.
+#[derive(Debug)]
+pub struct Cell {
+    pub colspan: usize,
+    pub content: CellContent,
+    pub halign: HAlign,
+    pub rowspan: usize,
+    pub style: CellStyle,
+    pub valign: VAlign,
+}
+
+/// The content of a table cell, which depends on its style.
+#[derive(Debug)]
+pub enum CellContent {
+    /// The blocks of a cell with the AsciiDoc style.
+    Blocks(Vec<Block>),
+    /// The source of a cell with the literal style.
+    Literal(String),
+    /// The paragraphs of the other cells, separated by empty lines.
+    Paragraphs(Vec<Text>),
+}
+
+/// The style of the cells of a table column, from the letter of its specification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellStyle {
+    AsciiDoc,
+    Default,
+    Emphasis,
+    Header,
+    Literal,
+    Monospace,
+    Strong,
+    Verse,
+}
+
+impl CellStyle {
+    /// Get the style from its letter, like `m` for monospace.
+    pub fn from_letter(letter: char) -> Option<CellStyle> {
+        let style =
+            match letter {
+                'a' => CellStyle::AsciiDoc,
+                'd' => CellStyle::Default,
+                'e' => CellStyle::Emphasis,
+                'h' => CellStyle::Header,
+                'l' => CellStyle::Literal,
+                'm' => CellStyle::Monospace,
+                's' => CellStyle::Strong,
+                'v' => CellStyle::Verse,
+                _ => return None,
+            };
+        Some(style)
+    }
+}
+
+/// The specification of a table column, from the `cols` attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSpec {
+    pub halign: HAlign,
+    pub style: CellStyle,
+    pub valign: VAlign,
+    /// The width, relative to the width of the other columns.
+    pub width: u32,
+}
+
+impl Default for ColumnSpec {
+    fn default() -> Self {
+        ColumnSpec {
+            halign: HAlign::Left,
+            style: CellStyle::Default,
+            valign: VAlign::Top,
+            width: 1,
+        }
+    }
+}
+
+/// An item of a description list: a term with its description and the blocks attached to it.
+#[derive(Debug)]
+pub struct DescriptionItem {
+    pub blocks: Vec<Block>,
+    pub description: Text,
+    pub term: Text,
+}
+
+/// The horizontal alignment of the content of a table cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HAlign {
+    Center,
+    Left,
+    Right,
+}
+
+impl HAlign {
+    /// Get the name of the alignment, as used in the HTML classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            HAlign::Center => "center",
+            HAlign::Left => "left",
+            HAlign::Right => "right",
+        }
+    }
+}
+
+/// The header of a document: its title and the author and revision lines following it.
+#[derive(Debug)]
+pub struct Header {
+    pub authors: Vec<Author>,
+    pub revdate: Option<String>,
+    pub revnumber: Option<String>,
+    pub revremark: Option<String>,
+    pub title: Text,
 }
 
 /// This is a recursive node structure that represents part of a asciidoctor document.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Node {
+    /// An admonition block, like an example block with the `NOTE` style, with its blocks.
+    Admonition(AdmonitionKind, Vec<Block>, Vec<Attribute>),
+    /// An attribute entry: the value is `None` when the attribute is unset.
+    AttributeEntry(String, Option<String>),
+    DescriptionList(Vec<DescriptionItem>, Vec<Attribute>),
+    /// An example block, delimited by `====`, with its blocks.
+    Example(Vec<Block>, Vec<Attribute>),
+    Header(Header),
+    /// A thematic break, `'''` or `---`, which each backend renders its own way, like `<hr>` in HTML.
     HorizontalRule,
+    /// A block image, like `image::sunset.jpg[Sunset]`, with its target.
+    Image {
+        attributes: Vec<Attribute>,
+        target: String,
+    },
+    /// A listing block, delimited by `----`, like source code, whose content is kept verbatim.
+    Listing(String, Vec<Attribute>),
+    /// A literal block, like an indented paragraph, whose content is kept verbatim.
+    Literal(String, Vec<Attribute>),
+    /// An audio or video block, like `video::clip.mp4[]`, with its target.
+    Media {
+        attributes: Vec<Attribute>,
+        kind: MediaKind,
+        target: String,
+    },
     PageBreak,
-    Paragraph(Text),
+    Paragraph(Text, Vec<Attribute>),
+    /// A passthrough block, delimited by `++++`, whose content is output as is by the HTML backend,
+    /// after its `subs`, like `c` for the special characters.
+    PassthroughBlock {
+        attributes: Vec<Attribute>,
+        content: String,
+        subs: Vec<String>,
+    },
+    /// The blocks between the document header and the first section of a document which has
+    /// sections, without the attribute entries following the header.
+    Preamble(Vec<Block>),
+    /// Some content which the backends output as is, like the HTML code of a custom block macro.
+    Raw(String, Vec<Attribute>),
+    Section(Section),
+    /// A math block.
+    Stem {
+        attributes: Vec<Attribute>,
+        kind: StemKind,
+        content: String,
+    },
+    Table(Table),
+    /// A `toc::[]` macro, where the table of contents is placed with `:toc: macro`.
+    Toc(Vec<Attribute>),
+    UnorderedList(Vec<ListItem>, Vec<Attribute>),
+}
+
+/// A table, with its rows of cells.
+#[derive(Debug)]
+pub struct Table {
+    pub attributes: Vec<Attribute>,
+    pub columns: Vec<ColumnSpec>,
+    pub rows: Vec<Vec<Cell>>,
 }
 
 /// A text contains words, links, bold text, …
@@ -48,15 +313,227 @@ impl Text {
             items,
         }
     }
+
+    /// Merge the consecutive words and spaces into single words, with the runs of spaces collapsed
+    /// into a single space, in this text and in the texts it contains, like the text of bold text.
+    /// The other items, like bold text, stay separate.
+    pub fn normalize(&mut self) {
+        let mut items: Vec<Item> = Vec::with_capacity(self.items.len());
+        let mut after_space = false;
+        for mut item in self.items.drain(..) {
+            let is_space = matches!(item, Item::Space);
+            let piece =
+                match item {
+                    Item::Space if after_space => continue,
+                    Item::Space => " ".to_string(),
+                    Item::Word(ref mut word) => mem::take(word),
+                    Item::Mark(ref mut text) | Item::Span { ref mut text, .. } | Item::Tag(_, ref mut text, _) => {
+                        text.normalize();
+                        after_space = false;
+                        items.push(item);
+                        continue;
+                    },
+                    _ => {
+                        after_space = false;
+                        items.push(item);
+                        continue;
+                    },
+                };
+            after_space = is_space;
+            if let Some(&mut Item::Word(ref mut last)) = items.last_mut() {
+                last.push_str(&piece);
+                continue;
+            }
+            items.push(Item::Word(piece));
+        }
+        self.items = items;
+    }
+
+    /// Get the text without its formatting, like the text of a section title used to generate its
+    /// ID.
+    pub fn plain(&self) -> String {
+        let mut plain = String::new();
+        for item in &self.items {
+            match *item {
+                Item::Button(ref label) => plain.push_str(label),
+                Item::Icon { .. } => (),
+                Item::IndexTerm { ref terms, visible: true, .. } => plain.push_str(&terms[0]),
+                Item::IndexTerm { .. } => (),
+                Item::Kbd(ref keys) => plain.push_str(&keys.join("+")),
+                Item::Link { ref target, ref text } => plain.push_str(text.as_ref().unwrap_or(target)),
+                Item::Passthrough { ref content, .. } => plain.push_str(content),
+                Item::SetAttribute(..) => (),
+                Item::NewLine | Item::Space => plain.push(' '),
+                Item::Mark(ref text) | Item::Span { ref text, .. } | Item::Tag(_, ref text, _) =>
+                    plain.push_str(&text.plain()),
+                Item::Menu(ref menu, ref items) => {
+                    plain.push_str(menu);
+                    for item in items {
+                        plain.push_str(" > ");
+                        plain.push_str(item);
+                    }
+                },
+                Item::Stem { ref content, .. } => plain.push_str(content),
+                Item::Word(ref word) => plain.push_str(word),
+                Item::Xref { ref id, ref text, .. } => plain.push_str(text.as_ref().unwrap_or(id)),
+            }
+        }
+        plain
+    }
 }
 
 /// A text item, like a word, link, bold text, …
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Item {
+    /// A button UI macro: `btn:[label]`.
+    Button(String),
+    /// An icon macro: `icon:heart[2x]`, with the size as its `size` attribute.
+    Icon {
+        attributes: Vec<Attribute>,
+        name: String,
+    },
+    /// An index term, from the primary one to the tertiary one: visible like `((term))`, or not like
+    /// `indexterm:[primary,secondary]` or `(((primary,secondary)))`.
+    /// The `id` is the one of the anchor an index links to.
+    IndexTerm {
+        id: String,
+        terms: Vec<String>,
+        visible: bool,
+    },
+    /// A keyboard UI macro: `kbd:[Ctrl+C]`.
+    Kbd(Vec<String>),
+    /// A link macro: `link:target[text]`, whose text is the target when it is empty.
+    Link {
+        target: String,
+        text: Option<String>,
+    },
+    /// A passthrough macro: `pass:[content]`, or `pass:a,c[content]` with the substitutions applied
+    /// to its content, like `a` or `attributes` for the attribute references and `c` or
+    /// `specialchars` for the special characters.
+    Passthrough {
+        content: String,
+        subs: Vec<String>,
+    },
+    /// An inline attribute entry: `set:name:value[]`, or `set:name!` to unset the attribute, in
+    /// which case the value is `None`.
+    SetAttribute(String, Option<String>),
     Space,
-    Mark(Text, Vec<Attribute>),
+    /// Highlighted text, between `#`.
+    Mark(Text),
+    /// A menu UI macro: `menu:File[Save]`.
+    Menu(String, Vec<String>),
+    /// The end of a line of a paragraph followed by another line.
+    NewLine,
+    /// A span of text between `#` with an attribute list, like `[.big #x]#text#`.
+    Span {
+        attributes: Vec<Attribute>,
+        text: Text,
+    },
+    /// An inline math macro: `stem:[x^2]`.
+    Stem {
+        kind: StemKind,
+        content: String,
+    },
     Tag(Tag, Text, Vec<Attribute>),
     Word(String),
+    /// A cross reference to the block with the ID `id`, like `<<id,text>>` or `xref:id[text]`.
+    /// The `path` is the one of the document of the block when it is another one, like
+    /// `other.adoc` in `xref:other.adoc#id[text]`, and the ID is then empty for the top of the
+    /// document.
+    Xref {
+        id: String,
+        path: Option<String>,
+        text: Option<String>,
+    },
+}
+
+/// An item of a list, with the blocks nested in it.
+#[derive(Debug)]
+pub struct ListItem {
+    pub blocks: Vec<Block>,
+    pub text: Text,
+}
+
+/// A section, with the blocks up to the next section of the same or a higher level.
+#[derive(Debug)]
+pub struct Section {
+    pub attributes: Vec<Attribute>,
+    pub blocks: Vec<Block>,
+    /// The ID, which is empty when the section has none, with `IdStyle::None`.
+    pub id: String,
+    /// The level, from 1 for `==` to 5 for `======`.
+    pub level: usize,
+    pub title: Text,
+}
+
+impl Section {
+    /// Check whether the section is a discrete heading, with the `discrete` or `float` style, which
+    /// has no blocks and is not part of the section hierarchy.
+    pub fn is_discrete(&self) -> bool {
+        self.attributes.iter().any(|attribute|
+            match *attribute {
+                Attribute::Style(ref style) => style == "discrete" || style == "float",
+                _ => false,
+            })
+    }
+}
+
+/// The kind of a media block, from the name of its macro.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+impl MediaKind {
+    /// Get the name of the macro of the media, which is also the name of its HTML element.
+    pub fn name(self) -> &'static str {
+        match self {
+            MediaKind::Audio => "audio",
+            MediaKind::Video => "video",
+        }
+    }
+}
+
+/// The notation of a math expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StemKind {
+    AsciiMath,
+    LatexMath,
+    /// The notation selected by the `:stem:` document attribute.
+    Stem,
+}
+
+impl StemKind {
+    /// Get the notation from the name of a macro or a block style.
+    pub fn from_name(name: &str) -> Option<StemKind> {
+        match name {
+            "asciimath" => Some(StemKind::AsciiMath),
+            "latexmath" => Some(StemKind::LatexMath),
+            "stem" => Some(StemKind::Stem),
+            _ => None,
+        }
+    }
+}
+
+/// The vertical alignment of the content of a table cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VAlign {
+    Bottom,
+    Middle,
+    Top,
+}
+
+impl VAlign {
+    /// Get the name of the alignment, as used in the HTML classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            VAlign::Bottom => "bottom",
+            VAlign::Middle => "middle",
+            VAlign::Top => "top",
+        }
+    }
 }
 
 /// An asciidoctor tag.
@@ -71,8 +548,8 @@ pub enum Tag {
 
 impl Tag {
     /// Convert the asciidoctor tag to the HTML tag string.
-    pub fn to_string(&self) -> &str {
-        match *self {
+    pub fn to_string(self) -> &'static str {
+        match self {
             Bold => "strong",
             InlineCode => "code",
             Italic => "em",
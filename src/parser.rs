@@ -21,14 +21,25 @@
 
 //! Parse asciidoctor.
 
+use std::collections::HashMap;
 use std::io::BufRead;
+use std::mem;
+use std::rc::Rc;
+use std::str;
 
+use attributes::AttributeRegistry;
+use diagnostic::{Diagnostic, Level};
 use error::{Error, Result};
+use ids::{IdRegistry, IdStyle};
 use lexer::Lexer;
-use node::{Attribute, Item, Node, Text};
-use node::Attribute::{Id, Role};
+use node::{self, AdmonitionKind, Attribute, Author, Block, Cell, CellContent, CellStyle, ColumnSpec, DescriptionItem,
+    Item, ListItem, MediaKind, Node, StemKind, Text};
+use node::Attribute::{Id, Named, Positional, Role, Style};
 use node::Node::*;
 use node::Tag::*;
+use position::{Pos, Span};
+use safe_mode::SafeMode;
+use table::{self, RawCell};
 use token::Token;
 use token::Token::*;
 
@@ -43,123 +54,1598 @@ macro_rules! parse_text_between {
 
 macro_rules! text_between {
     ($_self:expr, $token:ident) => {{
+        let pos = $_self.tokens.pos();
         $_self.eat($token)?;
-        let text = $_self.text_while(|token| token != &$token)?;
-        $_self.eat($token)?;
-        text
+        match $_self.formatted_text(&$token)? {
+            Some(text) => text,
+            None => {
+                // The delimiter is then a literal, followed by the text in the pending items.
+                $_self.warn(format!("unterminated formatting: {}", $token), pos)?;
+                return Ok(Item::Word($token.to_string()));
+            },
+        }
     }};
 }
 
-/// Asciidoctor parser.
-pub struct Parser<R: BufRead> {
-    tokens: Lexer<R>,
-}
+/// The styles of the blocks of AsciiDoc, like `source` in `[source,rust]`.
+const BLOCK_STYLES: &[&str] = &[
+    "CAUTION", "IMPORTANT", "NOTE", "TIP", "WARNING", "abstract", "appendix", "arabic", "asciimath", "bibliography",
+    "colophon", "comment", "decimal", "dedication", "discrete", "example", "float", "glossary", "horizontal", "index",
+    "latexmath", "listing", "literal", "loweralpha", "lowergreek", "lowerroman", "normal", "open", "partintro", "pass",
+    "preface", "qanda", "quote", "sidebar", "source", "stem", "upperalpha", "upperroman", "verse",
+];
+
+/// The start of a line beginning with a list marker, like `*`, or with text, like `*bold*`.
+enum LineStart {
+    Marker(String),
+    Text(Item),
+}
+
+/// The maximum depth of the nested blocks and includes, unless another one is specified.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// The handler of a custom block macro, which creates its node from its target and its attributes.
+type BlockMacroHandler = Rc<dyn Fn(&str, Vec<Attribute>) -> Node>;
+
+/// Asciidoctor parser.
+pub struct Parser<R: BufRead> {
+    /// The document attributes set so far, which enable some macros, like `:experimental:`.
+    attributes: AttributeRegistry,
+    /// The end of the block being parsed, when the tokens following it were already eaten.
+    block_end: Option<Pos>,
+    /// The handlers of the custom block macros, by name.
+    block_macros: HashMap<String, BlockMacroHandler>,
+    /// The number of blocks the blocks being parsed are nested in.
+    depth: usize,
+    diagnostics: Vec<Diagnostic>,
+    /// Whether the end of the document was found where a block may start, so that it does not end
+    /// a block which is not terminated.
+    eof_between_blocks: bool,
+    /// Whether no block was parsed yet, so that a document title starts the document header.
+    first_block: bool,
+    id_style: IdStyle,
+    ids: IdRegistry,
+    /// Whether the newline ending the last line of the last paragraph was eaten, while checking
+    /// whether the paragraph continues on the next line.
+    line_ended: bool,
+    /// The terms of the index terms found so far, in the order of the document.
+    index_terms: Vec<Vec<String>>,
+    /// The end of the list item followed by the last marker found by `list_marker`.
+    list_item_end: Pos,
+    /// The position of the last marker found by `list_marker`.
+    marker_pos: Pos,
+    max_depth: usize,
+    /// The blocks which were parsed while looking for the end of a section, the next one last.
+    next_blocks: Vec<(Node, Span)>,
+    /// The first items of a paragraph, which were eaten while looking for the next item of a list.
+    paragraph_start: Vec<Item>,
+    /// The position where the items of `paragraph_start` start.
+    paragraph_start_pos: Pos,
+    /// The items following a text item which was parsed with them, like the text of an unterminated
+    /// formatting, which follows its delimiter.
+    pending_items: Vec<Item>,
+    /// Whether the document header was just parsed, so that the next blocks may be a preamble.
+    preamble: bool,
+    safe_mode: SafeMode,
+    /// Whether the warnings are errors.
+    strict: bool,
+    tokens: Lexer<R>,
+    /// The IDs of the blocks referenced by the cross references of the document, with the position
+    /// of the cross references, to check that the blocks exist at the end of the document.
+    xrefs: Vec<(String, Pos)>,
+}
+
+impl<R: BufRead> Parser<R> {
+    /// Create a new parser from an iterator of tokens.
+    /// The resulting nodes can be fetched by calling `Parser::nodes()` which is an iterator over
+    /// asciidoctor nodes.
+    pub fn new(tokens: Lexer<R>) -> Self {
+        Parser::with_max_depth(tokens, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Create a new parser whose blocks are nested at most `max_depth` times: deeper blocks cause
+    /// an `Error::MaxDepthExceeded` instead of overflowing the stack.
+    pub fn with_max_depth(tokens: Lexer<R>, max_depth: usize) -> Self {
+        Parser {
+            attributes: AttributeRegistry::new(),
+            block_end: None,
+            block_macros: HashMap::new(),
+            depth: 0,
+            diagnostics: vec![],
+            eof_between_blocks: false,
+            first_block: true,
+            id_style: IdStyle::default(),
+            ids: IdRegistry::new(),
+            index_terms: vec![],
+            line_ended: false,
+            list_item_end: Pos::new(1, 1),
+            marker_pos: Pos::new(1, 1),
+            max_depth,
+            next_blocks: vec![],
+            paragraph_start: vec![],
+            paragraph_start_pos: Pos::new(1, 1),
+            pending_items: vec![],
+            preamble: false,
+            safe_mode: SafeMode::default(),
+            strict: false,
+            tokens,
+            xrefs: vec![],
+        }
+    }
+
+    /// Start with the document `attributes`, like the ones given to `convert()`, before the
+    /// attribute entries of the document change them.
+    pub fn attribute_registry(mut self, attributes: AttributeRegistry) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Make the warnings errors when `strict` is true, like the duplicate IDs, the unknown block
+    /// styles, the unterminated formatting and the cross references to IDs which do not exist.
+    /// This returns an `Error::Strict` at the first warning instead of reporting it in the
+    /// diagnostics.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Generate the IDs of the sections without an explicit ID in the `style`, like
+    /// `IdStyle::GitHub`, instead of the asciidoctor style.
+    pub fn id_style(mut self, style: IdStyle) -> Self {
+        self.id_style = style;
+        self
+    }
+
+    /// Use the `ids` registry for the IDs of the document, like one with another separator for the
+    /// generated IDs.
+    pub fn id_registry(mut self, ids: IdRegistry) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Register a custom block macro, like `video::intro.mp4[]` on its own line, whose node is
+    /// created by the `handler` from its target and its attributes, like `intro.mp4` and
+    /// `[Positional("width=640")]`, after the attributes of the lines preceding it.
+    pub fn register_block_macro<F>(mut self, name: &str, handler: F) -> Self
+        where F: Fn(&str, Vec<Attribute>) -> Node + 'static,
+    {
+        self.block_macros.insert(name.to_string(), Rc::new(handler));
+        self
+    }
+
+    /// Restrict the document with the `safe_mode`: with `SafeMode::Secure`, the raw passthroughs,
+    /// like `+++<b>raw</b>+++` and `pass:[<b>raw</b>]`, are escaped, for untrusted documents.
+    pub fn safe_mode(mut self, safe_mode: SafeMode) -> Self {
+        self.safe_mode = safe_mode;
+        self
+    }
+
+    /// Parse a paragraph starting with an admonition label, like `NOTE: text`, which is an admonition
+    /// whose first block is the rest of the paragraph: the blocks following a `+` line are attached
+    /// to it, like `NOTE: first` followed by `+` and `second`.
+    /// When the label is not followed by a colon and a space, this is a plain paragraph.
+    fn admonition_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let label = self.word_string()?;
+        let mut items = vec![Item::Word(label.clone())];
+        if *self.tokens.peek()? == Colon {
+            self.eat(Colon)?;
+            match *self.tokens.peek()? {
+                Space => {
+                    self.eat(Space)?;
+                    let kind = AdmonitionKind::from_style(&label).expect("admonition label");
+                    let mut blocks = vec![self.attached_block(|parser| parser.paragraph(vec![]))?];
+                    while self.continuation()? {
+                        blocks.push(self.attached_block(|parser| parser.block(vec![]))?);
+                    }
+                    return Ok(Admonition(kind, blocks, attributes));
+                },
+                Colon => {
+                    self.eat(Colon)?;
+                    match *self.tokens.peek()? {
+                        Space | NewLine => return self.description_list(items, attributes),
+                        _ => items.push(Item::Word("::".to_string())),
+                    }
+                },
+                _ => items.push(Item::Word(":".to_string())),
+            }
+        }
+        let (items, is_term) = self.line_or_term(items)?;
+        if is_term {
+            return self.description_list(items, attributes);
+        }
+        self.paragraph_from(items, attributes)
+    }
+
+    /// Parse a block attached to the one being parsed with `parse`, like a block following a `+` line,
+    /// along with its span.
+    fn attached_block<F: FnOnce(&mut Self) -> Result<Node>>(&mut self, parse: F) -> Result<Block> {
+        let start = self.tokens.pos();
+        self.block_end = None;
+        let node = parse(self)?;
+        // The end is kept for the span of the block this one is attached to.
+        let span = Span {
+            end: self.block_end.unwrap_or_else(|| self.tokens.end_pos()),
+            start,
+        };
+        Ok(Block::new(node, Some(span)))
+    }
+
+    /// Parse the author line of the document header, like `First Last <email>; Other Author`.
+    fn author_line(&mut self) -> Result<Vec<Author>> {
+        let line = String::from_utf8(self.tokens.line()?)?;
+        let authors = line.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(author)
+            .collect();
+        Ok(authors)
+    }
+
+    /// Parse an attribute entry like `:name: value`, `:name!:` or `:!name:`.
+    fn attribute_entry(&mut self, _attributes: Vec<Attribute>) -> Result<Node> {
+        self.eat(Colon)?;
+        let name = self.word_string()?;
+        self.eat(Colon)?;
+        let mut value = self.source_while(|token| token != &NewLine)?;
+        self.eat(NewLine)?;
+        // A value ending with a backslash continues on the next line.
+        while value.trim_end().ends_with('\\') {
+            let start = value.trim_end().len() - 1;
+            value.truncate(start);
+            let line = self.source_while(|token| token != &NewLine)?;
+            self.eat(NewLine)?;
+            value = format!("{} {}", value.trim_end(), line.trim());
+        }
+        if let Some(name) = name.strip_prefix('!').or_else(|| name.strip_suffix('!')) {
+            self.attributes.unset(name);
+            Ok(AttributeEntry(name.to_string(), None))
+        }
+        else {
+            let value = value.trim().to_string();
+            self.attributes.set(&name, &value);
+            Ok(AttributeEntry(name, Some(value)))
+        }
+    }
+
+    /// Parse attributes and the node following it.
+    fn attributes(&mut self) -> Result<Vec<Attribute>> {
+        let mut attributes = vec![];
+        if *self.tokens.peek()? == OpenSquareBracket {
+            let pos = self.tokens.pos();
+            let content = self.macro_content()?;
+            attributes =
+                match content.strip_prefix('[') {
+                    Some(anchor) => {
+                        self.eat(CloseSquareBracket)?;
+                        anchor_attributes(anchor)
+                    },
+                    None => attribute_list(&content),
+                };
+            if let Some(id) = id(&attributes) {
+                if !self.ids.register(id) {
+                    self.warn(format!("duplicate ID: {}", id), pos)?;
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// Parse an attribute list on its own line, which applies to the next block, or at the start of a
+    /// paragraph, where it applies to the first text item.
+    fn attributed_block(&mut self, mut attributes: Vec<Attribute>) -> Result<Node> {
+        let pos = self.tokens.pos();
+        let mut line_attributes = self.attributes()?;
+        if *self.tokens.peek()? == NewLine {
+            if let Some(style) = style(&line_attributes) {
+                if !BLOCK_STYLES.contains(&style) {
+                    self.warn(format!("unknown block style: {}", style), pos)?;
+                }
+            }
+            self.eat(NewLine)?;
+            attributes.append(&mut line_attributes);
+            return self.block(attributes);
+        }
+        let mut items = vec![self.text_item(line_attributes)?];
+        items.append(&mut self.pending_items);
+        self.paragraph_from(items, attributes)
+    }
+
+    /// Parse a block with the attributes found on the lines preceding it.
+    fn block(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let first_block = self.first_block;
+        self.first_block = false;
+        self.line_ended = false;
+        if let Err(Error::Eof) = self.tokens.peek() {
+            self.eof_between_blocks = true;
+            return Err(Error::Eof);
+        }
+        let func =
+            match *self.tokens.peek()? {
+                Colon => {
+                    self.first_block = first_block;
+                    Self::attribute_entry
+                },
+                OpenSquareBracket => Self::attributed_block,
+                TripleApos => Self::horizontal_rule,
+                TripleLt if first_block && self.depth == 0 => Self::leading_page_break,
+                TripleLt => Self::page_break,
+                Space => Self::literal_paragraph,
+                NewLine => {
+                    self.first_block = first_block;
+                    self.tokens.token()?;
+                    Self::block
+                },
+                Word(ref word) if word == b"=" && first_block => Self::header,
+                Word(ref word) if is_block_title(word) => Self::block_title,
+                Word(ref word) if word == b"====" => Self::example_or_section,
+                Word(ref word) if is_section_marker(word) => Self::section,
+                Word(ref word) if word == b"++++" => Self::passthrough_block,
+                Word(ref word) if word == b"----" => Self::listing_block,
+                Word(ref word) if word == b"---" => Self::horizontal_rule,
+                Word(ref word) if is_table_delimiter(word) => Self::table,
+                ref token if is_paragraph_start(token) && is_verbatim_style(&attributes) => Self::verbatim_paragraph,
+                ref token if is_paragraph_start(token) && paragraph_subs(&attributes).is_some() =>
+                    Self::unsubstituted_paragraph,
+                Word(ref word) if word == b"-" => Self::list_or_paragraph,
+                Word(ref word) if is_block_macro(word, &self.block_macros) => Self::block_macro_or_paragraph,
+                Word(ref word) if str::from_utf8(word).ok().and_then(AdmonitionKind::from_style).is_some() =>
+                    Self::admonition_paragraph,
+                Star | DoubleStar => Self::list_or_paragraph,
+                Backquote | Caret | CloseSquareBracket | ConstrainedPassthrough(_) | DoubleBackquote |
+                    DoubleUnderscore | NumberSign | Passthrough(_) | RawPassthrough(_) | Tilde | Token::Error(_) |
+                    Underscore | Word(_) | Xref(_) =>
+                    Self::paragraph,
+            };
+        func(self, attributes)
+    }
+
+    /// Parse a block macro, like `image::sunset.jpg[Sunset]`, `video::clip.mp4[]`, `toc::[]` or a
+    /// custom one, which overrides the built-in one of the same name, or a paragraph starting with
+    /// the name of such a macro.
+    fn block_macro_or_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let name = self.word_string()?;
+        let mut items = vec![Item::Word(name.clone())];
+        if *self.tokens.peek()? == Colon {
+            self.eat(Colon)?;
+            if *self.tokens.peek()? != Colon {
+                items.push(Item::Word(":".to_string()));
+            }
+            else {
+                self.eat(Colon)?;
+                let (is_term, has_brackets) =
+                    match *self.tokens.peek()? {
+                        Space | NewLine => (true, false),
+                        ref token => (false, *token == OpenSquareBracket),
+                    };
+                if is_term {
+                    return self.description_list(items, attributes);
+                }
+                if let Some(handler) = self.block_macros.get(&name).cloned() {
+                    return self.custom_block_macro(&*handler, attributes);
+                }
+                match name.as_str() {
+                    "audio" => return self.media(MediaKind::Audio, attributes),
+                    "image" => return self.image(attributes),
+                    "video" => return self.media(MediaKind::Video, attributes),
+                    _ => (),
+                }
+                if has_brackets {
+                    return self.toc_macro(attributes);
+                }
+                items.push(Item::Word("::".to_string()));
+            }
+        }
+        let (items, is_term) = self.line_or_term(items)?;
+        if is_term {
+            return self.description_list(items, attributes);
+        }
+        self.paragraph_from(items, attributes)
+    }
+
+    /// Parse the title of a block, like `.Title`, on the line preceding it: it is kept in the
+    /// `title` attribute of the block.
+    fn block_title(&mut self, mut attributes: Vec<Attribute>) -> Result<Node> {
+        let title = self.source_while(|token| token != &NewLine)?;
+        self.eat(NewLine)?;
+        attributes.push(Named("title".to_string(), title[1..].to_string()));
+        self.block(attributes)
+    }
+
+    /// Parse bold text whose opening star, or double star, was just eaten.
+    fn bold_from(&mut self, star: Token) -> Result<Item> {
+        let text = self.text_while(|token| token != &star)?;
+        self.eat(star)?;
+        Ok(Item::Tag(Bold, text, vec![]))
+    }
+
+    /// Parse a colon, which is plain text when it is not part of a macro.
+    fn colon(&mut self, _attributes: Vec<Attribute>) -> Result<Item> {
+        self.eat(Colon)?;
+        Ok(Item::Word(":".to_string()))
+    }
+
+    /// Eat a `+` line directly following a block, which attaches the next block to the enclosing one,
+    /// and return whether there was one.
+    /// A line starting with a `+` followed by text is the start of the next paragraph instead.
+    fn continuation(&mut self) -> Result<bool> {
+        match self.tokens.peek() {
+            Ok(Word(word)) if word == b"+" => (),
+            Ok(_) | Err(Error::Eof) => return Ok(false),
+            Err(error) => return Err(error),
+        }
+        let line_pos = self.tokens.pos();
+        let end = self.tokens.end_pos();
+        self.eat(Word(b"+".to_vec()))?;
+        if *self.tokens.peek()? == NewLine {
+            self.eat(NewLine)?;
+            return Ok(true);
+        }
+        self.paragraph_start = vec![Item::Word("+".to_string())];
+        self.paragraph_start_pos = line_pos;
+        self.block_end = Some(end);
+        Ok(false)
+    }
+
+    /// Parse a custom block macro, like `video::intro.mp4[]`, with the `handler` registered for its
+    /// name, which was just eaten with its colons.
+    fn custom_block_macro(&mut self, handler: &dyn Fn(&str, Vec<Attribute>) -> Node, mut attributes: Vec<Attribute>)
+        -> Result<Node>
+    {
+        let target = self.source_while(|token| token != &OpenSquareBracket && token != &NewLine)?;
+        let content = self.macro_content()?;
+        attributes.append(&mut attribute_list(&content));
+        Ok(handler(&target, attributes))
+    }
+
+    /// Parse a description list, whose first term was just parsed, like `Term:: description`.
+    /// The description is the text following the term, with the lines which are not terms following
+    /// it, or a list directly following the term.
+    fn description_list(&mut self, first_term: Vec<Item>, attributes: Vec<Attribute>) -> Result<Node> {
+        let mut items = vec![];
+        let mut term = first_term;
+        loop {
+            let mut description = vec![];
+            let mut line = self.text_while(|token| token != &NewLine)?;
+            append_line(&mut description, &mut line.items);
+            let mut blocks = vec![];
+            let mut next_term = None;
+            let mut newlines = self.eat_newlines()?;
+            while let Some(count) = newlines {
+                if count > 1 && !is_paragraph_start(self.tokens.peek()?) {
+                    break;
+                }
+                let mut line_start = vec![];
+                if count == 1 && *self.tokens.peek()? == Word(b"+".to_vec()) {
+                    self.eat(Word(b"+".to_vec()))?;
+                    if *self.tokens.peek()? == NewLine {
+                        // A list continuation: the next block is attached to the item.
+                        self.eat(NewLine)?;
+                        blocks.push(self.attached_block(|parser| parser.block(vec![]))?);
+                        newlines = self.eat_newlines()?;
+                        continue;
+                    }
+                    line_start.push(Item::Word("+".to_string()));
+                }
+                let is_marker =
+                    line_start.is_empty() &&
+                    match *self.tokens.peek()? {
+                        Star | DoubleStar => true,
+                        Word(ref word) => word == b"-",
+                        _ => false,
+                    };
+                let line_pos = self.tokens.pos();
+                let end = self.tokens.end_pos();
+                if is_marker {
+                    match self.marker()? {
+                        LineStart::Marker(marker) => {
+                            self.block_end = None;
+                            let list = self.unordered_list_from(marker, vec![])?;
+                            if !blocks.is_empty() {
+                                // Only one list is attached to an item, the next one follows the
+                                // description list.
+                                let span = Span {
+                                    end: self.block_end.take().unwrap_or_else(|| self.tokens.end_pos()),
+                                    start: line_pos,
+                                };
+                                self.next_blocks.push((list, span));
+                                self.block_end = Some(end);
+                                break;
+                            }
+                            let span = Span {
+                                end: self.block_end.unwrap_or_else(|| self.tokens.end_pos()),
+                                start: line_pos,
+                            };
+                            blocks.push(Block::new(list, Some(span)));
+                            if !self.paragraph_start.is_empty() {
+                                break;
+                            }
+                            newlines =
+                                match self.tokens.peek() {
+                                    Ok(_) => Some(2),
+                                    Err(Error::Eof) => None,
+                                    Err(error) => return Err(error),
+                                };
+                            continue;
+                        },
+                        LineStart::Text(item) => line_start.push(item),
+                    }
+                }
+                let (mut line, is_term) = self.line_or_term(line_start)?;
+                if is_term {
+                    next_term = Some(line);
+                    break;
+                }
+                if count > 1 || !blocks.is_empty() {
+                    self.paragraph_start = line;
+                    self.paragraph_start_pos = line_pos;
+                    self.block_end = Some(end);
+                    break;
+                }
+                append_line(&mut description, &mut line);
+                newlines = self.eat_newlines()?;
+            }
+            items.push(DescriptionItem {
+                blocks,
+                description: Text::new(description),
+                term: Text::new(term),
+            });
+            match next_term {
+                Some(next_term) => term = next_term,
+                None => break,
+            }
+        }
+        Ok(DescriptionList(items, attributes))
+    }
+
+    /// Eat the newlines ending the current line and the empty lines following it.
+    /// Return the number of newlines eaten, or `None` at the end of the file.
+    fn eat_newlines(&mut self) -> Result<Option<usize>> {
+        let mut count = if mem::replace(&mut self.line_ended, false) { 1 } else { 0 };
+        loop {
+            match self.tokens.peek() {
+                Ok(&NewLine) => self.eat(NewLine)?,
+                Ok(_) => return Ok(Some(count)),
+                Err(Error::Eof) => return Ok(None),
+                Err(error) => return Err(error),
+            }
+            count += 1;
+        }
+    }
+
+    /// Eat the consecutive page breaks and the empty lines between them, and return whether the end
+    /// of the file follows them.
+    fn eat_page_breaks(&mut self) -> Result<bool> {
+        loop {
+            self.eat(TripleLt)?;
+            match self.eat_newlines()? {
+                None => return Ok(true),
+                Some(_) if *self.tokens.peek()? == TripleLt => (),
+                Some(_) => return Ok(false),
+            }
+        }
+    }
+
+    /// Get the diagnostics reported so far, like duplicate IDs.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Get the document attributes in effect at the current point of the parsing, like the
+    /// metadata of the whole document once it is parsed: the ones set by its attribute entries and
+    /// the ones of its header, like `doctitle`, `author` and `revnumber`.
+    pub fn document_attributes(&self) -> &AttributeRegistry {
+        &self.attributes
+    }
+
+    /// Eat the expected token or return an error if a different token is found.
+    fn eat(&mut self, expected: Token) -> Result<()> {
+        let token = self.tokens.token()?;
+        if token != expected {
+            return Err(self.unexpected_token(&expected.to_string())); // FIXME: does not show the right actual token because it was consumed by the call to token().
+        }
+        Ok(())
+    }
+
+    /// Parse the text of a formatting up to its closing `delimiter`, which is eaten, across the lines
+    /// of its paragraph. Return None when the paragraph ends before the delimiter, with the text
+    /// found so far in the pending items.
+    fn formatted_text(&mut self, delimiter: &Token) -> Result<Option<Text>> {
+        let mut items = vec![];
+        loop {
+            match self.tokens.peek() {
+                Ok(token) if token == delimiter => break,
+                Ok(&NewLine) => {
+                    match self.tokens.peek_char_after_token() {
+                        Ok(b'\n') | Ok(b'\r') | Err(Error::Eof) => {
+                            self.pending_items = items;
+                            return Ok(None);
+                        },
+                        Ok(_) => (),
+                        Err(error) => return Err(error),
+                    }
+                    self.eat(NewLine)?;
+                    items.push(Item::NewLine);
+                },
+                Ok(_) => self.push_text_item(&mut items)?,
+                Err(Error::Eof) => {
+                    self.pending_items = items;
+                    return Ok(None);
+                },
+                Err(error) => return Err(error),
+            }
+        }
+        self.tokens.token()?;
+        Ok(Some(Text::new(items)))
+    }
+
+    /// Parse a line starting with `====`: the start of an example block, which is an admonition block
+    /// with a style like `NOTE`, or the title of a section of level 3.
+    fn example_or_section(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let marker = self.word_string()?;
+        if *self.tokens.peek()? != NewLine {
+            return self.section_from(marker.len() - 1, attributes);
+        }
+        self.eat(NewLine)?;
+        let start = self.tokens.pos();
+        let content = self.lines_until(b"====")?;
+        let blocks = self.nested_blocks(&content, start)?;
+        match style(&attributes).and_then(AdmonitionKind::from_style) {
+            Some(kind) => Ok(Admonition(kind, blocks, attributes)),
+            None => Ok(Example(blocks, attributes)),
+        }
+    }
+
+    /// Parse the document header: the document title and the author and revision lines following it.
+    fn header(&mut self, _attributes: Vec<Attribute>) -> Result<Node> {
+        self.eat(Word(b"=".to_vec()))?;
+        self.eat(Space)?;
+        let title = self.text_while(|token| token != &NewLine)?;
+        self.eat(NewLine)?;
+        let mut header = node::Header {
+            authors: vec![],
+            revdate: None,
+            revnumber: None,
+            revremark: None,
+            title,
+        };
+        if self.header_line_follows()? {
+            header.authors = self.author_line()?;
+            if self.header_line_follows()? {
+                self.revision_line(&mut header)?;
+            }
+        }
+        self.set_header_attributes(&header);
+        self.preamble = self.depth == 0;
+        Ok(Header(header))
+    }
+
+    /// Check whether the next line is an author or revision line of the header, rather than an empty
+    /// line or an attribute entry.
+    fn header_line_follows(&mut self) -> Result<bool> {
+        match self.tokens.peek_char() {
+            Ok(b'\n') | Ok(b'\r') | Ok(b':') | Err(Error::Eof) => Ok(false),
+            Ok(_) => Ok(true),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Parse an horizontal rule: `'''`, or `---` alone on its line, which otherwise starts a paragraph.
+    fn horizontal_rule(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        if *self.tokens.peek()? == TripleApos {
+            self.eat(TripleApos)?;
+            return Ok(HorizontalRule);
+        }
+        self.eat(Word(b"---".to_vec()))?;
+        if *self.tokens.peek()? == NewLine {
+            return Ok(HorizontalRule);
+        }
+        self.paragraph_from(vec![Item::Word("---".to_string())], attributes)
+    }
+
+    /// Parse the target and the attribute list of a block image whose `image::` prefix was just
+    /// eaten: the positional attributes are the alternate text, the width and the height.
+    fn image(&mut self, mut attributes: Vec<Attribute>) -> Result<Node> {
+        let target = self.source_while(|token| token != &OpenSquareBracket && token != &NewLine)?;
+        let content = self.macro_content()?;
+        attributes.append(&mut macro_attributes(&content, &["alt", "width", "height"]));
+        Ok(Image {
+            attributes,
+            target,
+        })
+    }
+
+    /// Parse an icon macro: `icon:heart[]`, whose positional attribute is the size, like `2x` in
+    /// `icon:heart[2x]`.
+    fn icon_macro(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let literal = format!("{}:", name);
+        let icon =
+            match *self.tokens.peek()? {
+                Word(_) => self.word_string()?,
+                _ => return Ok(Item::Word(literal)),
+            };
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}{}", literal, icon)));
+        }
+        let content = self.macro_content()?;
+        Ok(Item::Icon {
+            attributes: macro_attributes(&content, &["size"]),
+            name: icon,
+        })
+    }
+
+    /// Register an index term made of `terms`, from the primary one to the tertiary one.
+    fn index_term(&mut self, terms: Vec<String>, visible: bool) -> Item {
+        let id = self.ids.unique(format!("_indexterm_{}", self.index_terms.len() + 1));
+        self.index_terms.push(terms.clone());
+        Item::IndexTerm {
+            id,
+            terms,
+            visible,
+        }
+    }
+
+    /// Parse an index term macro: `indexterm:[primary,secondary,tertiary]`, which is not visible,
+    /// or `indexterm2:[term]`, which is.
+    fn index_term_macro(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let literal = format!("{}:", name);
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(literal));
+        }
+        let content = self.macro_content()?;
+        let visible = name == "indexterm2";
+        let terms =
+            if visible {
+                vec![content.trim().to_string()]
+            }
+            else {
+                content.split(',').map(|term| unquote(term.trim()).to_string()).collect()
+            };
+        if terms.iter().any(String::is_empty) {
+            return Ok(Item::Word(format!("{}[{}]", literal, content)));
+        }
+        Ok(self.index_term(terms, visible))
+    }
+
+    /// Parse an index term in parentheses, starting with `word`: `((term))`, which is visible, or
+    /// `(((primary,secondary)))`, which is not.
+    /// The text is kept as is when the parentheses are not closed at the end of a word of the line.
+    fn index_term_in_parentheses(&mut self, word: String) -> Result<Item> {
+        let mut source = word;
+        while source.len() < 4 || !source.ends_with("))") {
+            match *self.tokens.peek()? {
+                NewLine => return Ok(Item::Word(source)),
+                Space => source.push(' '),
+                ref token => source.push_str(&token.to_string()),
+            }
+            self.tokens.token()?;
+        }
+        let visible = !source.starts_with("(((");
+        if visible {
+            let term = source[2..source.len() - 2].trim().to_string();
+            return Ok(self.index_term(vec![term], true));
+        }
+        if source.len() < 6 || !source.ends_with(")))") {
+            return Ok(Item::Word(source));
+        }
+        let terms: Vec<_> = source[3..source.len() - 3].split(',').map(|term| term.trim().to_string()).collect();
+        if terms.iter().any(String::is_empty) {
+            return Ok(Item::Word(source));
+        }
+        Ok(self.index_term(terms, false))
+    }
+
+    /// Get the terms of the index terms parsed so far, in the order of the document, to build
+    /// an index.
+    pub fn index_terms(&self) -> &[Vec<String>] {
+        &self.index_terms
+    }
+
+    parse_text_between!(bold, Star, Bold);
+    parse_text_between!(inline_code, Backquote, InlineCode);
+    parse_text_between!(italic, Underscore, Italic);
+    parse_text_between!(subscript, Tilde, SubScript);
+    parse_text_between!(superscript, Caret, SuperScript);
+    parse_text_between!(unconstrained_bold, DoubleStar, Bold);
+    parse_text_between!(unconstrained_inline_code, DoubleBackquote, InlineCode);
+    parse_text_between!(unconstrained_italic, DoubleUnderscore, Italic);
+
+    /// Parse an inline math macro: `stem:[…]`, `asciimath:[…]` or `latexmath:[…]`.
+    fn inline_stem(&mut self, name: String, kind: StemKind) -> Result<Item> {
+        self.eat(Colon)?;
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}:", name)));
+        }
+        let content = self.macro_content()?;
+        Ok(Item::Stem {
+            kind,
+            content,
+        })
+    }
+
+    /// Ignore the page breaks at the start of the document, before its first block.
+    fn leading_page_break(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        self.eat_page_breaks()?;
+        self.block(attributes)
+    }
+
+    /// Parse a link macro: `link:target[text]`, whose target spans the tokens up to the `[`, like in
+    /// `link:https://example.org/[text]`.
+    /// The target is a passthrough when it contains special characters, like in
+    /// `link:++https://example.org/?a=[1]++[text]`.
+    fn link(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let passthrough =
+            match *self.tokens.peek()? {
+                ConstrainedPassthrough(ref target) | Passthrough(ref target) =>
+                    Some(String::from_utf8(target.clone())?),
+                _ => None,
+            };
+        let target =
+            match passthrough {
+                Some(target) => {
+                    self.tokens.token()?;
+                    target
+                },
+                None => self.source_while(|token| token != &OpenSquareBracket && token != &NewLine && token != &Space)?,
+            };
+        if target.is_empty() {
+            return Ok(Item::Word(format!("{}:", name)));
+        }
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}:{}", name, target)));
+        }
+        let text = self.macro_content()?;
+        let text =
+            if text.is_empty() {
+                None
+            }
+            else {
+                Some(text)
+            };
+        Ok(Item::Link {
+            target,
+            text,
+        })
+    }
+
+    /// Parse an email link macro: `mailto:user@example.org[text]`, whose text defaults to the email
+    /// address, or `mailto:user@example.org[text,subject,body]`, whose subject and body are added to
+    /// the query of the link.
+    fn mailto(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let address =
+            match *self.tokens.peek()? {
+                Word(ref address) => String::from_utf8(address.clone())?,
+                _ => return Ok(Item::Word(format!("{}:", name))),
+            };
+        self.tokens.token()?;
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}:{}", name, address)));
+        }
+        let attributes = macro_attributes(&self.macro_content()?, &["text", "subject", "body"]);
+        let mut target = format!("mailto:{}", address);
+        let mut separator = '?';
+        for name in &["subject", "body"] {
+            if let Some(value) = named(&attributes, name) {
+                target.push_str(&format!("{}{}={}", separator, name, percent_encode(value)));
+                separator = '&';
+            }
+        }
+        Ok(Item::Link {
+            target,
+            text: Some(named(&attributes, "text").unwrap_or(&address).to_string()),
+        })
+    }
+
+    /// Parse the items of a list whose marker, like `*` or `-`, was just eaten, along with the lists
+    /// nested in them.
+    /// Also return the marker which ended the list when it belongs to one of the `parents` lists.
+    fn list_items(&mut self, marker: &str, parents: &mut Vec<String>) -> Result<(Vec<ListItem>, Option<String>)> {
+        let mut items = vec![];
+        loop {
+            self.eat(Space)?;
+            let text = self.text_while(|token| token != &NewLine)?;
+            let mut item = ListItem {
+                blocks: vec![],
+                text,
+            };
+            let mut next_marker = self.list_marker(&mut item)?;
+            while let Some(nested_marker) = next_marker.take() {
+                if nested_marker == marker || parents.contains(&nested_marker) {
+                    next_marker = Some(nested_marker);
+                    break;
+                }
+                check_depth(self.depth + 1, self.max_depth)?;
+                let start = self.marker_pos;
+                self.block_end = None;
+                self.depth += 1;
+                parents.push(marker.to_string());
+                let nested = self.list_items(&nested_marker, parents);
+                parents.pop();
+                self.depth -= 1;
+                let (nested_items, ending_marker) = nested?;
+                let end =
+                    match ending_marker {
+                        Some(_) => self.list_item_end,
+                        None => self.block_end.unwrap_or_else(|| self.tokens.end_pos()),
+                    };
+                let span = Span {
+                    end,
+                    start,
+                };
+                item.blocks.push(Block::new(UnorderedList(nested_items, vec![]), Some(span)));
+                next_marker = ending_marker;
+            }
+            items.push(item);
+            match next_marker {
+                Some(ref next_marker) if next_marker == marker => (),
+                _ => return Ok((items, next_marker)),
+            }
+        }
+    }
+
+    /// Parse the items of a line starting with `items`, up to the end of the line or up to a `::`
+    /// followed by a space or a newline, in which case the line is the term of a description list.
+    fn line_or_term(&mut self, mut items: Vec<Item>) -> Result<(Vec<Item>, bool)> {
+        loop {
+            let mut line = self.text_while(|token| token != &NewLine && token != &Colon)?;
+            items.append(&mut line.items);
+            if *self.tokens.peek()? != Colon {
+                return Ok((items, false));
+            }
+            self.eat(Colon)?;
+            if *self.tokens.peek()? != Colon {
+                items.push(Item::Word(":".to_string()));
+                continue;
+            }
+            self.eat(Colon)?;
+            match *self.tokens.peek()? {
+                Space | NewLine => return Ok((items, true)),
+                _ => items.push(Item::Word("::".to_string())),
+            }
+        }
+    }
+
+    /// Read the lines of a delimited block as is, up to the closing delimiter or the end of the file.
+    fn delimited_content(&mut self, delimiter: &[u8]) -> Result<String> {
+        self.eat(Word(delimiter.to_vec()))?;
+        self.eat(NewLine)?;
+        self.lines_until(delimiter)
+    }
+
+    /// Read the lines of a delimited block as is, up to its closing `delimiter`.
+    fn lines_until(&mut self, delimiter: &[u8]) -> Result<String> {
+        let mut lines = vec![];
+        loop {
+            match self.tokens.line() {
+                Ok(ref line) if line == delimiter => break,
+                Ok(line) => lines.push(String::from_utf8(line)?),
+                Err(Error::Eof) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Parse a block starting with a list marker: a list when the marker is followed by a space,
+    /// otherwise a paragraph, like one starting with bold text.
+    fn list_or_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        match self.marker()? {
+            LineStart::Marker(marker) => self.unordered_list_from(marker, attributes),
+            LineStart::Text(item) => self.paragraph_from(vec![item], attributes),
+        }
+    }
+
+    /// Eat the end of a list item and the marker of the next item, if any.
+    /// The lines directly following the item which do not start with a marker are appended to its
+    /// text.
+    fn list_marker(&mut self, item: &mut ListItem) -> Result<Option<String>> {
+        let mut newlines = 0;
+        loop {
+            let is_marker =
+                match self.tokens.peek() {
+                    Ok(&NewLine) => {
+                        self.eat(NewLine)?;
+                        newlines += 1;
+                        continue;
+                    },
+                    Ok(&Star) | Ok(&DoubleStar) => true,
+                    Ok(Word(word)) => word == b"-",
+                    Ok(_) => false,
+                    Err(Error::Eof) => return Ok(None),
+                    Err(error) => return Err(error),
+                };
+            if !is_marker && newlines > 1 {
+                return Ok(None);
+            }
+            let line_pos = self.tokens.pos();
+            let end = self.tokens.end_pos();
+            if is_marker {
+                match self.marker()? {
+                    LineStart::Marker(marker) => {
+                        self.list_item_end = end;
+                        self.marker_pos = line_pos;
+                        return Ok(Some(marker));
+                    },
+                    LineStart::Text(first_item) =>
+                        if newlines > 1 {
+                            self.paragraph_start = vec![first_item];
+                            self.paragraph_start_pos = line_pos;
+                            self.block_end = Some(end);
+                            return Ok(None);
+                        }
+                        else {
+                            item.text.items.push(Item::Space);
+                            item.text.items.push(first_item);
+                        },
+                }
+            }
+            else {
+                item.text.items.push(Item::Space);
+            }
+            let mut line = self.text_while(|token| token != &NewLine)?;
+            item.text.items.append(&mut line.items);
+            newlines = 0;
+        }
+    }
+
+    /// Eat the marker of a list item, like `-` or `**`, or the first text item when the marker is not
+    /// followed by a space.
+    fn marker(&mut self) -> Result<LineStart> {
+        let mut marker = String::new();
+        loop {
+            match *self.tokens.peek()? {
+                Star | DoubleStar if !marker.starts_with('-') => (),
+                Word(ref word) if word == b"-" && marker.is_empty() => (),
+                Space => return Ok(LineStart::Marker(marker)),
+                _ => break,
+            }
+            marker.push_str(&self.tokens.token()?.to_string());
+        }
+        match marker.as_str() {
+            "*" => Ok(LineStart::Text(self.bold_from(Star)?)),
+            "**" => Ok(LineStart::Text(self.bold_from(DoubleStar)?)),
+            _ => Ok(LineStart::Text(Item::Word(marker))),
+        }
+    }
+
+    /// Parse an inline macro escaped with a backslash, like `\kbd:[X]`, whose name was just eaten: it
+    /// is kept as is, without the backslash.
+    fn escaped_macro(&mut self, name: String) -> Result<Item> {
+        let mut literal = name;
+        let target = self.source_while(|token| token != &OpenSquareBracket && token != &Space && token != &NewLine)?;
+        literal.push_str(&target);
+        if *self.tokens.peek()? == OpenSquareBracket {
+            literal.push_str(&self.source_while(|token| token != &CloseSquareBracket && token != &NewLine)?);
+            if *self.tokens.peek()? == CloseSquareBracket {
+                self.eat(CloseSquareBracket)?;
+                literal.push(']');
+            }
+        }
+        Ok(Item::Word(literal))
+    }
+
+    /// Parse the content between the square brackets of a macro, as is.
+    fn macro_content(&mut self) -> Result<String> {
+        self.eat(OpenSquareBracket)?;
+        let content = self.source_while(|token| token != &CloseSquareBracket && token != &NewLine)?;
+        self.eat(CloseSquareBracket)?;
+        Ok(content)
+    }
+
+    /// Parse a mark, or a span when the text has an attribute list, like `[.role]#text#`.
+    fn mark(&mut self, attributes: Vec<Attribute>) -> Result<Item> {
+        let text = text_between!(self, NumberSign);
+        if attributes.is_empty() {
+            Ok(Item::Mark(text))
+        }
+        else {
+            Ok(Item::Span {
+                attributes,
+                text,
+            })
+        }
+    }
+
+    /// Parse an audio or video block macro, like `video::clip.mp4[]`, whose name was just eaten with
+    /// its colons.
+    /// The positional attributes of a video are named `poster`, `width` and `height`.
+    fn media(&mut self, kind: MediaKind, mut attributes: Vec<Attribute>) -> Result<Node> {
+        let target = self.source_while(|token| token != &OpenSquareBracket && token != &NewLine)?;
+        let content = self.macro_content()?;
+        let positional_names: &[&str] =
+            match kind {
+                MediaKind::Audio => &[],
+                MediaKind::Video => &["poster", "width", "height"],
+            };
+        attributes.append(&mut macro_attributes(&content, positional_names));
+        Ok(Media {
+            attributes,
+            kind,
+            target,
+        })
+    }
+
+    /// Create a parser for some content which is not part of the token stream of the document,
+    /// like a table cell, nested in the block being parsed, which starts at the position `start` of
+    /// the document, so that the positions of its errors and diagnostics are in the document.
+    /// The IDs, the diagnostics, the index terms and the cross references of this parser are given
+    /// to the nested one, until `nested_end()` takes them back.
+    fn nested<'a>(&mut self, content: &'a str, start: Pos) -> Parser<&'a [u8]> {
+        let lexer = Lexer::starting_at(content.as_bytes(), start);
+        let mut parser = Parser::with_max_depth(lexer, self.max_depth)
+            .attribute_registry(self.attributes.clone())
+            .id_registry(mem::take(&mut self.ids))
+            .id_style(self.id_style)
+            .safe_mode(self.safe_mode)
+            .strict(self.strict);
+        parser.block_macros = self.block_macros.clone();
+        parser.depth = self.depth + 1;
+        parser.diagnostics = mem::take(&mut self.diagnostics);
+        parser.index_terms = mem::take(&mut self.index_terms);
+        parser.xrefs = mem::take(&mut self.xrefs);
+        parser
+    }
+
+    /// Parse the blocks of some content which is not part of the token stream of the document,
+    /// like a table cell with the AsciiDoc style.
+    fn nested_blocks(&mut self, content: &str, start: Pos) -> Result<Vec<Block>> {
+        check_depth(self.depth + 1, self.max_depth)?;
+        // The lexer needs a newline at the end of its input.
+        let content = format!("{}\n", content);
+        let mut parser = self.nested(&content, start);
+        let mut blocks = vec![];
+        let result =
+            loop {
+                match parser.node_with_span() {
+                    Ok((node, span)) => blocks.push(Block::new(node, Some(span))),
+                    Err(Error::Eof) => break Ok(blocks),
+                    Err(error) => break Err(error),
+                }
+            };
+        self.nested_end(parser);
+        result
+    }
+
+    /// Take back the IDs, the diagnostics, the index terms and the cross references which were
+    /// given to the `nested` parser, with the ones it found.
+    fn nested_end(&mut self, nested: Parser<&[u8]>) {
+        self.diagnostics = nested.diagnostics;
+        self.ids = nested.ids;
+        self.index_terms = nested.index_terms;
+        self.xrefs = nested.xrefs;
+    }
+
+    /// Parse some text which is not part of the token stream of the document, like a table cell.
+    fn nested_text(&mut self, content: &str, start: Pos) -> Result<Text> {
+        // The lexer needs a newline at the end of its input.
+        let content = format!("{}\n", content);
+        let mut parser = self.nested(&content, start);
+        let result = parser.text_to_eof();
+        self.nested_end(parser);
+        result
+    }
+
+    /// An iterator over the nodes of the document.
+    pub fn node(&mut self) -> Result<Node> {
+        self.node_with_span().map(|(node, _)| node)
+    }
+
+    /// Like `node`, but also get the span of the node in the document.
+    /// The blocks nested in the node, like the blocks of a section or of a table cell, hold their own
+    /// span.
+    /// At the end of the document, this reports the cross references to IDs which do not exist.
+    pub fn node_with_span(&mut self) -> Result<(Node, Span)> {
+        if self.preamble {
+            self.preamble = false;
+            self.preamble()?;
+        }
+        match self.spanned_block() {
+            // The cross references found in nested blocks are checked at the end of the document.
+            Err(Error::Eof) if self.depth == 0 => {
+                for (id, pos) in mem::take(&mut self.xrefs) {
+                    if !self.ids.contains(&id) {
+                        self.warn(format!("cross reference to a missing ID: {}", id), pos)?;
+                    }
+                }
+                Err(Error::Eof)
+            },
+            result => result,
+        }
+    }
+
+    /// Parse a listing block, delimited by `----`.
+    fn listing_block(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let content = self.delimited_content(b"----")?;
+        Ok(Listing(content, attributes))
+    }
+
+    /// Parse a paragraph whose first line is indented, which is a literal block: its lines are kept
+    /// verbatim, without their common indentation.
+    fn literal_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        self.eat(Space)?;
+        let mut lines = vec![format!(" {}", String::from_utf8(self.tokens.line()?)?)];
+        loop {
+            match self.tokens.peek_char() {
+                Ok(b'\n') | Err(Error::Eof) => break,
+                Ok(_) => lines.push(String::from_utf8(self.tokens.line()?)?),
+                Err(error) => return Err(error),
+            }
+        }
+        let indentation = lines.iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start_matches(' ').len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<_> = lines.iter()
+            .map(|line| line.get(indentation..).unwrap_or("").trim_end())
+            .collect();
+        Ok(Literal(lines.join("\n"), attributes))
+    }
+
+    /// Parse a page break, `<<<`, with the ones following it, possibly after empty lines, which make
+    /// a single page break.
+    /// The page breaks at the end of the document are ignored.
+    fn page_break(&mut self, _attributes: Vec<Attribute>) -> Result<Node> {
+        if self.eat_page_breaks()? && self.depth == 0 {
+            self.eof_between_blocks = true;
+            return Err(Error::Eof);
+        }
+        Ok(PageBreak)
+    }
+
+    /// Parse a paragraph.
+    /// A paragraph whose first line ends with `::` is a description list instead.
+    fn paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let (items, is_term) = self.line_or_term(vec![])?;
+        if is_term {
+            return self.description_list(items, attributes);
+        }
+        self.paragraph_from(items, attributes)
+    }
+
+    /// Parse the rest of a paragraph starting with the specified items, up to an empty line or a
+    /// line starting another block.
+    /// Its consecutive words and spaces are merged, with `Text::normalize()`.
+    fn paragraph_from(&mut self, mut items: Vec<Item>, attributes: Vec<Attribute>) -> Result<Node> {
+        loop {
+            let mut line = self.text_while(|node| node != &NewLine)?;
+            items.append(&mut line.items);
+            if !self.paragraph_continues()? {
+                self.line_ended = true;
+                break;
+            }
+            items.push(Item::NewLine);
+        }
+        let mut text = Text::new(items);
+        text.normalize();
+        Ok(Paragraph(text, attributes))
+    }
+
+    /// Eat the newline ending a line of a paragraph and check whether the next line continues the
+    /// paragraph: it does not when it is empty or when it can start another block, like a list
+    /// item.
+    fn paragraph_continues(&mut self) -> Result<bool> {
+        self.eat(NewLine)?;
+        match self.tokens.peek_char() {
+            Ok(b'\n') | Ok(b'\r') | Err(Error::Eof) => return Ok(false),
+            Ok(_) => (),
+            Err(error) => return Err(error),
+        }
+        let token = self.tokens.peek()?;
+        let continues =
+            match *token {
+                Star | DoubleStar => false,
+                // A `+` line attaches the next block to a list item.
+                Word(ref word) => word != b"-" && word != b"+" && !is_block_title(word) && is_paragraph_start(token),
+                _ => is_paragraph_start(token),
+            };
+        Ok(continues)
+    }
+
+    /// Parse the blocks following the document header up to the first section, which are grouped in
+    /// a `Preamble` when there is such a section, and put them in the next blocks.
+    /// The attribute entries directly following the header are not part of the preamble.
+    fn preamble(&mut self) -> Result<()> {
+        let mut blocks = vec![];
+        let section = loop {
+            match self.spanned_block() {
+                Ok((Section(section), span)) if !section.is_discrete() => break Some((Section(section), span)),
+                Ok(block) => blocks.push(block),
+                Err(Error::Eof) => break None,
+                Err(error) => return Err(error),
+            }
+        };
+        let entries = blocks.iter().take_while(|&(node, _)| matches!(*node, AttributeEntry(..))).count();
+        let content = blocks.split_off(entries);
+        match section {
+            Some(section) => {
+                self.next_blocks.push(section);
+                if let (Some(first), Some(last)) = (content.first(), content.last()) {
+                    let span = Span {
+                        end: last.1.end,
+                        start: first.1.start,
+                    };
+                    let blocks = content.into_iter().map(|(node, span)| Block::new(node, Some(span))).collect();
+                    self.next_blocks.push((Preamble(blocks), span));
+                }
+            },
+            None => self.next_blocks.extend(content.into_iter().rev()),
+        }
+        self.next_blocks.extend(blocks.into_iter().rev());
+        Ok(())
+    }
 
-impl<R: BufRead> Parser<R> {
-    /// Create a new parser from an iterator of tokens.
-    /// The resulting nodes can be fetched by calling `Parser::nodes()` which is an iterator over
-    /// asciidoctor nodes.
-    pub fn new(tokens: Lexer<R>) -> Self {
-        Parser {
-            tokens,
+    /// Set the document attributes defined by the `header`, like `doctitle` and `author`.
+    fn set_header_attributes(&mut self, header: &node::Header) {
+        self.attributes.set("doctitle", &header.title.plain());
+        if let Some(author) = header.authors.first() {
+            self.attributes.set("author", &author.fullname());
+            self.attributes.set("firstname", &author.firstname);
+            self.attributes.set("authorinitials", &author.initials);
+        }
+        let author = header.authors.first();
+        let optional_attributes = [
+            ("email", author.and_then(|author| author.email.as_ref())),
+            ("lastname", author.and_then(|author| author.lastname.as_ref())),
+            ("middlename", author.and_then(|author| author.middlename.as_ref())),
+            ("revdate", header.revdate.as_ref()),
+            ("revnumber", header.revnumber.as_ref()),
+            ("revremark", header.revremark.as_ref()),
+        ];
+        for &(name, value) in &optional_attributes {
+            if let Some(value) = value {
+                self.attributes.set(name, value);
+            }
         }
     }
 
-    /// Parse an attribute.
-    fn attribute(&mut self) -> Result<Attribute> {
-        let attribute =
-            match self.tokens.token()? {
-                NumberSign => {
-                    if let Word(word) = self.tokens.token()? {
-                        Id(String::from_utf8(word)?)
-                    } else {
-                        return Err(self.unexpected_token("ident")) // FIXME: does not show the right actual token because it was consumed by the call to token().
-                    }
-                },
-                Word(word) => Role(String::from_utf8(word)?),
-                _ => return Err(self.unexpected_token("ident")), // FIXME: does not show the right actual token because it was consumed by the call to token().
+    /// Parse a section: its title, like `== Title`, and the blocks up to the next section of the same
+    /// or a higher level.
+    fn section(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let level = self.word_string()?.len() - 1;
+        self.section_from(level, attributes)
+    }
+
+    /// Parse a section of `level` whose marker was just eaten.
+    fn section_from(&mut self, level: usize, attributes: Vec<Attribute>) -> Result<Node> {
+        self.eat(Space)?;
+        let title = self.text_while(|token| token != &NewLine)?;
+        let id =
+            match id(&attributes) {
+                Some(id) => id.to_string(),
+                None =>
+                    match self.id_style.id(&title.plain()) {
+                        Some(id) => self.ids.unique_in_style(id, self.id_style),
+                        None => String::new(),
+                    },
             };
-        Ok(attribute)
+        let mut blocks = vec![];
+        let mut end = self.tokens.end_pos();
+        let discrete = style(&attributes) == Some("discrete") || style(&attributes) == Some("float");
+        if !discrete {
+            loop {
+                match self.spanned_block() {
+                    Ok((Section(section), span)) =>
+                        if section.level <= level && !section.is_discrete() {
+                            self.next_blocks.push((Section(section), span));
+                            break;
+                        }
+                        else {
+                            blocks.push(Block::new(Section(section), Some(span)));
+                            end = span.end;
+                        },
+                    Ok((node, span)) => {
+                        blocks.push(Block::new(node, Some(span)));
+                        end = span.end;
+                    },
+                    Err(Error::Eof) => break,
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        self.block_end = Some(end);
+        Ok(Section(node::Section {
+            attributes,
+            blocks,
+            id,
+            level,
+            title,
+        }))
     }
 
-    /// Parse attributes and the node following it.
-    fn attributes(&mut self) -> Result<Vec<Attribute>> {
-        let mut attributes = vec![];
-        if *self.tokens.peek()? == OpenSquareBracket {
-            self.eat(OpenSquareBracket)?;
-            attributes.push(self.attribute()?);
-            // TODO: other attributes.
-            self.eat(CloseSquareBracket)?;
+    /// Skip the tokens up to the next empty line, where the next block starts, to continue parsing
+    /// the document after an error.
+    /// This returns an `Error::Eof` when there is no block left.
+    pub fn skip_to_next_block(&mut self) -> Result<()> {
+        self.block_end = None;
+        self.first_block = false;
+        self.line_ended = false;
+        self.paragraph_start.clear();
+        loop {
+            let at_line_start = self.tokens.pos().column == 1;
+            if self.tokens.token()? == NewLine && at_line_start {
+                return Ok(());
+            }
         }
-        Ok(attributes)
     }
 
-    /// Eat the expected token or return an error if a different token is found.
-    fn eat(&mut self, expected: Token) -> Result<()> {
-        let token = self.tokens.token()?;
-        if token != expected {
-            return Err(self.unexpected_token(&expected.to_string())); // FIXME: does not show the right actual token because it was consumed by the call to token().
+    /// Parse the next block with its span, which starts after the empty lines preceding it.
+    fn spanned_block(&mut self) -> Result<(Node, Span)> {
+        if let Some(block) = self.next_blocks.pop() {
+            return Ok(block);
         }
-        Ok(())
+        let (start, node) =
+            if !self.paragraph_start.is_empty() {
+                let items = self.paragraph_start.drain(..).collect();
+                (self.paragraph_start_pos, self.paragraph_from(items, vec![])?)
+            }
+            else {
+                while *self.tokens.peek()? == NewLine {
+                    self.eat(NewLine)?;
+                }
+                let start = self.tokens.pos();
+                self.eof_between_blocks = false;
+                match self.block(vec![]) {
+                    Ok(node) => (start, node),
+                    Err(Error::Eof) if !self.eof_between_blocks => return Err(Error::UnexpectedEof(start)),
+                    Err(error) => return Err(error),
+                }
+            };
+        let span = Span {
+            end: self.block_end.take().unwrap_or_else(|| self.tokens.end_pos()),
+            start,
+        };
+        Ok((node, span))
     }
 
-    /// Parse an horizontal rule.
-    fn horizontal_rule(&mut self) -> Result<Node> {
-        self.eat(TripleApos)?;
-        Ok(HorizontalRule)
+    /// Get the source text of the tokens while the predicate returns true.
+    fn source_while<F: Fn(&Token) -> bool>(&mut self, predicate: F) -> Result<String> {
+        let mut source = String::new();
+        while predicate(self.tokens.peek()?) {
+            source.push_str(&self.tokens.token()?.to_source());
+        }
+        Ok(source)
     }
 
-    parse_text_between!(bold, Star, Bold);
-    parse_text_between!(inline_code, Backquote, InlineCode);
-    parse_text_between!(italic, Underscore, Italic);
-    parse_text_between!(subscript, Tilde, SubScript);
-    parse_text_between!(superscript, Caret, SuperScript);
-    parse_text_between!(unconstrained_bold, DoubleStar, Bold);
-    parse_text_between!(unconstrained_inline_code, DoubleBackquote, InlineCode);
-    parse_text_between!(unconstrained_italic, DoubleUnderscore, Italic);
+    /// Parse a passthrough macro, like `pass:[<u>raw</u>]`, whose content is kept as is, or
+    /// `pass:a,c[{name} <b>]`, which only applies the substitutions listed before its content, like
+    /// `a` for the attribute references and `c` for the special characters.
+    fn pass_macro(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let mut literal = format!("{}:", name);
+        let mut subs = vec![];
+        if let Word(_) = *self.tokens.peek()? {
+            let word = self.word_string()?;
+            literal.push_str(&word);
+            subs = word.split(',').map(|sub| sub.trim().to_string()).collect();
+        }
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(literal));
+        }
+        let content = self.macro_content()?;
+        Ok(self.raw_passthrough(content, subs))
+    }
 
-    /// Parse a mark.
-    fn mark(&mut self, attributes: Vec<Attribute>) -> Result<Item> {
-        let text = text_between!(self, NumberSign);
-        Ok(Item::Mark(text, attributes))
+    /// Parse an inline passthrough, like `+text+` or `++text++`, whose content is not formatted, or a
+    /// raw one, like `+++<b>raw</b>+++`, whose content is not escaped either.
+    fn passthrough(&mut self, _attributes: Vec<Attribute>) -> Result<Item> {
+        match self.tokens.token()? {
+            ConstrainedPassthrough(content) | Passthrough(content) => Ok(Item::Word(String::from_utf8(content)?)),
+            RawPassthrough(content) => Ok(self.raw_passthrough(String::from_utf8(content)?, vec![])),
+            _ => Err(Error::Msg("Should have got passthrough token".to_string())), // TODO: better error.
+        }
     }
 
-    /// An iterator over the nodes of the document.
-    pub fn node(&mut self) -> Result<Node> {
-        let func =
-            match *self.tokens.peek()? {
-                TripleApos => Self::horizontal_rule,
-                TripleLt => Self::page_break,
-                NewLine | Space => {
-                    self.tokens.token()?;
-                    Self::node
-                },
-                Backquote | Caret | CloseSquareBracket | DoubleBackquote | DoubleStar |
-                    DoubleUnderscore | NumberSign | OpenSquareBracket | Star | Tilde |
-                    Underscore | Word(_) =>
-                    Self::paragraph,
-            };
-        func(self)
+    /// Parse a passthrough block, delimited by `++++`, which contains math when it has a stem style.
+    /// Otherwise, its content is output as is, after the substitutions of its `subs` attribute, like
+    /// `[subs=attributes]`.
+    fn passthrough_block(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let content = self.delimited_content(b"++++")?;
+        if let Some(kind) = style(&attributes).and_then(StemKind::from_name) {
+            return Ok(Stem {
+                attributes,
+                kind,
+                content,
+            });
+        }
+        let subs = named(&attributes, "subs")
+            .map(|subs| subs.split(',').map(|sub| sub.trim().to_string()).collect())
+            .unwrap_or_default();
+        Ok(PassthroughBlock {
+            subs: self.passthrough_subs(subs),
+            attributes,
+            content,
+        })
     }
 
-    /// Parse a page break
-    fn page_break(&mut self) -> Result<Node> {
-        self.eat(TripleLt)?;
-        Ok(PageBreak)
+    /// Get the substitutions applied to a passthrough: its `subs`, with the special characters
+    /// escaped anyway when the safe mode does not allow raw passthroughs.
+    fn passthrough_subs(&self, mut subs: Vec<String>) -> Vec<String> {
+        let escaped = subs.iter().any(|sub| sub == "c" || sub == "specialchars");
+        if !escaped && !self.safe_mode.allows_raw_passthroughs() {
+            subs.push("c".to_string());
+        }
+        subs
     }
 
-    /// Parse a paragraph.
-    fn paragraph(&mut self) -> Result<Node> {
-        let mut items = vec![];
-        loop {
-            let mut line = self.text_while(|node| node != &NewLine)?;
-            // End of paragraph on an empty line.
-            if line.items.is_empty() {
-                break;
+    /// Parse a text item and push it to `items`, followed by the pending items.
+    fn push_text_item(&mut self, items: &mut Vec<Item>) -> Result<()> {
+        let item = self.text_item(vec![])?;
+        items.push(item);
+        items.append(&mut self.pending_items);
+        Ok(())
+    }
+
+    /// Create a passthrough item applying only the `subs` to its `content`.
+    fn raw_passthrough(&self, content: String, subs: Vec<String>) -> Item {
+        Item::Passthrough {
+            content,
+            subs: self.passthrough_subs(subs),
+        }
+    }
+
+    /// Parse the revision line of the document header, like `v1.0, 2020-05-01: remark`.
+    /// The version number is the part before the first comma, without its leading non-digits.
+    fn revision_line(&mut self, header: &mut node::Header) -> Result<()> {
+        let line = String::from_utf8(self.tokens.line()?)?;
+        let rest =
+            match line.find(',') {
+                Some(comma) => {
+                    let number = line[..comma].trim_start_matches(|char: char| !char.is_ascii_digit());
+                    header.revnumber = Some(number.trim().to_string());
+                    &line[comma + 1..]
+                },
+                None => &line[..],
+            };
+        let (date, remark) =
+            match rest.find(':') {
+                Some(colon) => (&rest[..colon], Some(rest[colon + 1..].trim())),
+                None => (rest, None),
+            };
+        let date = date.trim();
+        if header.revnumber.is_none() && date.starts_with('v') {
+            header.revnumber = Some(date[1..].to_string());
+        }
+        else if !date.is_empty() {
+            header.revdate = Some(date.to_string());
+        }
+        header.revremark = remark.map(ToString::to_string);
+        Ok(())
+    }
+
+    /// Parse an inline attribute entry: `set:name:value[]`, or `set:name!` to unset the attribute.
+    /// The text is kept as is when the tokens do not form a macro.
+    fn set_attribute(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let attribute =
+            match *self.tokens.peek()? {
+                Word(_) => self.word_string()?,
+                _ => return Ok(Item::Word(format!("{}:", name))),
+            };
+        if let Some(attribute) = attribute.strip_suffix('!') {
+            if *self.tokens.peek()? == OpenSquareBracket {
+                self.eat(OpenSquareBracket)?;
+                self.eat(CloseSquareBracket)?;
             }
-            items.append(&mut line.items);
+            self.attributes.unset(attribute);
+            return Ok(Item::SetAttribute(attribute.to_string(), None));
         }
-        Ok(Paragraph(Text::new(items)))
+        let literal = format!("{}:{}", name, attribute);
+        if *self.tokens.peek()? != Colon {
+            return Ok(Item::Word(literal));
+        }
+        self.eat(Colon)?;
+        let value = self.source_while(|token| token != &OpenSquareBracket && token != &NewLine && token != &Space)?;
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}:{}", literal, value)));
+        }
+        self.eat(OpenSquareBracket)?;
+        self.eat(CloseSquareBracket)?;
+        self.attributes.set(&attribute, &value);
+        Ok(Item::SetAttribute(attribute, Some(value)))
     }
 
     /// Parse a space.
@@ -180,22 +1666,127 @@ impl<R: BufRead> Parser<R> {
             match *self.tokens.peek()? {
                 Backquote => Self::inline_code,
                 Caret => Self::superscript,
+                Colon => Self::colon,
                 DoubleBackquote => Self::unconstrained_inline_code,
                 DoubleStar => Self::unconstrained_bold,
                 DoubleUnderscore => Self::unconstrained_italic,
                 NumberSign => Self::mark,
                 OpenSquareBracket => Self::text_item,
+                ConstrainedPassthrough(_) | Passthrough(_) | RawPassthrough(_) => Self::passthrough,
                 Space => Self::space,
                 Star => Self::bold,
                 Tilde => Self::subscript,
                 Underscore => Self::italic,
                 Word(_) => Self::word,
+                Xref(_) => Self::xref,
                 ref node => return Err(Error::Msg(format!("Should have got text token, but got {:?}", node))), // TODO: better error.
             };
         let item = func(self, attributes)?;
         Ok(item)
     }
 
+    /// Parse a table, delimited by `|===`, or by `!===` for a table nested in an AsciiDoc cell,
+    /// whose cells are separated by `!`. The `separator` attribute overrides the separator.
+    /// Without a `cols` attribute, the table has as many columns as there are cells on its first
+    /// line.
+    fn table(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let delimiter: &[u8] =
+            if *self.tokens.peek()? == Word(b"!===".to_vec()) {
+                b"!==="
+            }
+            else {
+                b"|==="
+            };
+        let pos = self.tokens.pos();
+        let content = self.delimited_content(delimiter)?;
+        let separator = named(&attributes, "separator")
+            .and_then(|separator| separator.chars().next())
+            .unwrap_or(delimiter[0] as char);
+        let columns = named(&attributes, "cols").map(|cols| (cols, table::column_specs(cols)));
+        let columns =
+            match columns {
+                Some((_, Some(columns))) => columns,
+                columns => {
+                    if let Some((cols, None)) = columns {
+                        self.warn(format!("too many columns: {}", cols), pos)?;
+                    }
+                    vec![ColumnSpec::default(); table::column_count(&content, separator)]
+                },
+            };
+        let mut rows = vec![];
+        for row in table::rows(table::cells(&content, separator), columns.len()) {
+            let mut cells = vec![];
+            for (index, cell) in row {
+                let column = columns.get(index).cloned().unwrap_or_default();
+                // The body of the table starts on the line following its delimiter.
+                let start = offset_pos(&content, cell.start, Pos::new(pos.line + 1, 1));
+                cells.push(self.table_cell(cell, &column, start)?);
+            }
+            rows.push(cells);
+        }
+        Ok(Table(node::Table {
+            attributes,
+            columns,
+            rows,
+        }))
+    }
+
+    /// Parse the content of a table cell, which starts at `start`, according to its style, or the
+    /// style of its column.
+    fn table_cell(&mut self, cell: RawCell, column: &ColumnSpec, start: Pos) -> Result<Cell> {
+        let style = cell.spec.style.unwrap_or(column.style);
+        let content =
+            match style {
+                CellStyle::AsciiDoc => CellContent::Blocks(self.nested_blocks(&cell.content, start)?),
+                CellStyle::Literal => CellContent::Literal(cell.content),
+                _ => {
+                    let mut paragraphs = vec![];
+                    let mut lines = vec![];
+                    let mut paragraph_start = start;
+                    for (index, line) in cell.content.lines().chain(Some("")).enumerate() {
+                        if !line.trim().is_empty() {
+                            if lines.is_empty() && index > 0 {
+                                paragraph_start = Pos::new(start.line + index, 1);
+                            }
+                            lines.push(line);
+                        }
+                        else if !lines.is_empty() {
+                            paragraphs.push(self.nested_text(&lines.join("\n"), paragraph_start)?);
+                            lines.clear();
+                        }
+                    }
+                    CellContent::Paragraphs(paragraphs)
+                },
+            };
+        Ok(Cell {
+            colspan: cell.spec.colspan,
+            content,
+            halign: cell.spec.halign.unwrap_or(column.halign),
+            rowspan: cell.spec.rowspan,
+            style,
+            valign: cell.spec.valign.unwrap_or(column.valign),
+        })
+    }
+
+    /// Parse the text up to the end of the file, where newlines are spaces.
+    fn text_to_eof(&mut self) -> Result<Text> {
+        let mut items = vec![];
+        loop {
+            match self.tokens.peek() {
+                Ok(&NewLine) => {
+                    self.eat(NewLine)?;
+                    if self.tokens.peek().is_ok() {
+                        items.push(Item::Space);
+                    }
+                },
+                Ok(_) => self.push_text_item(&mut items)?,
+                Err(Error::Eof) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Text::new(items))
+    }
+
     /// Parse text while the predicate returns true.
     fn text_while<F: Fn(&Token) -> bool>(&mut self, predicate: F) -> Result<Text> {
         let mut items = vec![];
@@ -211,12 +1802,19 @@ impl<R: BufRead> Parser<R> {
                 self.eat(NewLine)?;
                 continue;
             }
-            let item = self.text_item(vec![])?;
-            items.push(item);
+            self.push_text_item(&mut items)?;
         }
         Ok(Text::new(items))
     }
 
+    /// Parse a `toc::[]` macro, whose `::` was just eaten, which places the table of contents of
+    /// the document with `:toc: macro`.
+    fn toc_macro(&mut self, mut attributes: Vec<Attribute>) -> Result<Node> {
+        let content = self.macro_content()?;
+        attributes.append(&mut attribute_list(&content));
+        Ok(Toc(attributes))
+    }
+
     /// Return an UnexpectedToken error.
     fn unexpected_token(&mut self, expected: &str) -> Error {
         let actual = self.tokens.peek()
@@ -229,13 +1827,511 @@ impl<R: BufRead> Parser<R> {
         }
     }
 
-    /// Parse a single word.
+    /// Parse an unordered list whose first marker was just eaten.
+    fn unordered_list_from(&mut self, marker: String, attributes: Vec<Attribute>) -> Result<Node> {
+        let (items, _) = self.list_items(&marker, &mut vec![])?;
+        Ok(UnorderedList(items, attributes))
+    }
+
+    /// Parse a UI macro: `btn:[label]`, `kbd:[keys]` or `menu:name[item]`.
+    /// The text is kept as is when the tokens do not form a macro or when the `experimental`
+    /// attribute is not set.
+    fn ui_macro(&mut self, name: String) -> Result<Item> {
+        self.eat(Colon)?;
+        let mut literal = format!("{}:", name);
+        let mut menu = String::new();
+        if name == "menu" {
+            if let Word(_) = *self.tokens.peek()? {
+                menu = self.word_string()?;
+                literal.push_str(&menu);
+            }
+            else {
+                return Ok(Item::Word(literal));
+            }
+        }
+        if *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(literal));
+        }
+        let content = self.macro_content()?;
+        if !self.attributes.is_set("experimental") {
+            return Ok(Item::Word(format!("{}[{}]", literal, content)));
+        }
+        let content = content.trim();
+        let item =
+            match name.as_str() {
+                "btn" => Item::Button(content.to_string()),
+                "kbd" => Item::Kbd(keys(content)),
+                _ => Item::Menu(menu, menu_items(content)),
+            };
+        Ok(item)
+    }
+
+    /// Parse a paragraph whose `subs` attribute only enables the special characters or attributes
+    /// substitutions, like `[subs=none]`: its lines are kept as is, in a single passthrough item.
+    fn unsubstituted_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let subs = paragraph_subs(&attributes).unwrap_or_default();
+        let content = self.verbatim_lines()?;
+        let item = self.raw_passthrough(content, subs);
+        Ok(Paragraph(Text::new(vec![item]), attributes))
+    }
+
+    /// Parse a paragraph with the `literal`, `listing` or `source` style, like `[literal]`, which is a
+    /// literal or a listing block: its lines are kept verbatim, up to an empty line.
+    fn verbatim_paragraph(&mut self, attributes: Vec<Attribute>) -> Result<Node> {
+        let content = self.verbatim_lines()?;
+        if style(&attributes) == Some("literal") {
+            Ok(Literal(content, attributes))
+        }
+        else {
+            Ok(Listing(content, attributes))
+        }
+    }
+
+    /// Read the lines of a paragraph as is, up to an empty line.
+    fn verbatim_lines(&mut self) -> Result<String> {
+        let mut lines = vec![];
+        loop {
+            lines.push(self.source_while(|token| token != &NewLine)?);
+            self.eat(NewLine)?;
+            match self.tokens.peek() {
+                Ok(&NewLine) | Err(Error::Eof) => break,
+                Ok(_) => (),
+                Err(error) => return Err(error),
+            }
+        }
+        self.line_ended = true;
+        Ok(lines.join("\n"))
+    }
+
+    /// Report a warning found at `pos`, which is an error in strict mode.
+    fn warn(&mut self, message: String, pos: Pos) -> Result<()> {
+        if self.strict {
+            return Err(Error::Strict {
+                message,
+                pos,
+            });
+        }
+        self.diagnostics.push(Diagnostic {
+            level: Level::Warning,
+            message,
+            pos,
+        });
+        Ok(())
+    }
+
+    /// Parse a single word, or the macro it starts.
     fn word(&mut self, _attributes: Vec<Attribute>) -> Result<Item> {
+        let pos = self.tokens.pos();
+        let word = self.word_string()?;
+        if word.starts_with("((") {
+            return self.index_term_in_parentheses(word);
+        }
+        if *self.tokens.peek()? == Colon {
+            if let Some(name) = word.strip_prefix('\\').filter(|name| is_inline_macro(name)) {
+                return self.escaped_macro(name.to_string());
+            }
+            if let Some(kind) = StemKind::from_name(&word) {
+                return self.inline_stem(word, kind);
+            }
+            match word.as_str() {
+                "btn" | "kbd" | "menu" => return self.ui_macro(word),
+                "icon" => return self.icon_macro(word),
+                "indexterm" | "indexterm2" => return self.index_term_macro(word),
+                "link" => return self.link(word),
+                "mailto" => return self.mailto(word),
+                "pass" => return self.pass_macro(word),
+                "set" => return self.set_attribute(word),
+                "xref" => return self.xref_macro(word, pos),
+                _ => (),
+            }
+        }
+        Ok(Item::Word(word))
+    }
+
+    /// Parse a single word token as a string.
+    fn word_string(&mut self) -> Result<String> {
         if let Ok(Word(bytes)) = self.tokens.token() {
-            Ok(Item::Word(String::from_utf8(bytes)?))
+            Ok(String::from_utf8(bytes)?)
+        }
+        else {
+            Err(Error::Msg("Should have got word token".to_string())) // TODO: better error.
+        }
+    }
+
+    /// Parse a cross reference, like `<<id>>` or `<<id,text>>`.
+    fn xref(&mut self, _attributes: Vec<Attribute>) -> Result<Item> {
+        let pos = self.tokens.pos();
+        match self.tokens.token()? {
+            Xref(content) => {
+                let content = String::from_utf8(content)?;
+                let mut parts = content.splitn(2, ',');
+                let (path, id) = xref_target(parts.next().unwrap_or("").trim());
+                let text = parts.next().map(|text| text.trim().to_string());
+                self.xref_to(&id, &path, pos);
+                Ok(Item::Xref { id, path, text })
+            },
+            _ => Err(self.unexpected_token("<<")),
+        }
+    }
+
+    /// Parse a cross reference macro, like `xref:id[text]` or `xref:other.adoc#id[text]`.
+    /// The text is kept as is when the tokens do not form a macro.
+    fn xref_macro(&mut self, name: String, pos: Pos) -> Result<Item> {
+        self.eat(Colon)?;
+        let target = self.source_while(|token| token != &OpenSquareBracket && token != &NewLine && token != &Space)?;
+        if target.is_empty() || *self.tokens.peek()? != OpenSquareBracket {
+            return Ok(Item::Word(format!("{}:{}", name, target)));
+        }
+        let text = self.macro_content()?;
+        let text =
+            if text.is_empty() {
+                None
+            }
+            else {
+                Some(text)
+            };
+        let (path, id) = xref_target(&target);
+        self.xref_to(&id, &path, pos);
+        Ok(Item::Xref {
+            id,
+            path,
+            text,
+        })
+    }
+
+    /// Register a cross reference to the block with the ID `id` at `pos`, which is checked at the
+    /// end of the document when it is in this document.
+    fn xref_to(&mut self, id: &str, path: &Option<String>, pos: Pos) {
+        if path.is_none() && !id.is_empty() {
+            self.xrefs.push((id.to_string(), pos));
+        }
+    }
+}
+
+/// Append a line of text to the description of a description list item, without its indentation.
+fn append_line(description: &mut Vec<Item>, line: &mut Vec<Item>) {
+    let indentation = line.iter().take_while(|item| matches!(**item, Item::Space)).count();
+    line.drain(..indentation);
+    if line.is_empty() {
+        return;
+    }
+    if !description.is_empty() {
+        description.push(Item::Space);
+    }
+    description.append(line);
+}
+
+/// Parse an author of the author line, like `First Middle Last <email>`.
+/// The underscores in a name are replaced by spaces.
+fn author(entry: &str) -> Author {
+    let (names, email) =
+        match (entry.find('<'), entry.rfind('>')) {
+            (Some(start), Some(end)) if start < end =>
+                (entry[..start].trim(), Some(entry[start + 1..end].trim().to_string())),
+            _ => (entry, None),
+        };
+    let names: Vec<_> = names.split_whitespace()
+        .map(|name| name.replace('_', " "))
+        .collect();
+    let (firstname, middlename, lastname) =
+        match names.len() {
+            1 => (names[0].clone(), None, None),
+            2 => (names[0].clone(), None, Some(names[1].clone())),
+            3 => (names[0].clone(), Some(names[1].clone()), Some(names[2].clone())),
+            // An author with more names is only a first name.
+            _ => (names.join(" "), None, None),
+        };
+    let initials = Some(&firstname).into_iter()
+        .chain(middlename.iter())
+        .chain(lastname.iter())
+        .filter_map(|name| name.chars().next())
+        .collect();
+    Author {
+        email,
+        firstname,
+        initials,
+        lastname,
+        middlename,
+    }
+}
+
+/// Check that blocks nested `depth` times do not exceed the maximum depth.
+fn check_depth(depth: usize, max_depth: usize) -> Result<()> {
+    if depth > max_depth {
+        return Err(Error::MaxDepthExceeded(max_depth));
+    }
+    Ok(())
+}
+
+/// Get the ID of a block.
+fn id(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Id(ref id) => Some(id.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
+/// Parse some text which is not part of the token stream of the document, like a table cell,
+/// with the document `attributes` in effect there.
+pub fn inline_text(content: &str, attributes: &AttributeRegistry) -> Result<Text> {
+    // The lexer needs a newline at the end of its input.
+    let content = format!("{}\n", content);
+    let mut parser = Parser::new(Lexer::new(content.as_bytes())).attribute_registry(attributes.clone());
+    parser.text_to_eof()
+}
+
+/// Check whether a word is the name of a block macro: a built-in one, like `image`, or one of the
+/// custom `block_macros`.
+fn is_block_macro(word: &[u8], block_macros: &HashMap<String, BlockMacroHandler>) -> bool {
+    word == b"audio" || word == b"image" || word == b"toc" || word == b"video" ||
+        str::from_utf8(word).is_ok_and(|name| block_macros.contains_key(name))
+}
+
+/// Check whether a word starts the title of a block, like `.Title`, which is not an ellipsis.
+fn is_block_title(word: &[u8]) -> bool {
+    word.len() >= 2 && word[0] == b'.' && word[1] != b'.'
+}
+
+/// Check whether a word is the name of an inline macro, like `kbd` or `link`.
+fn is_inline_macro(name: &str) -> bool {
+    StemKind::from_name(name).is_some() ||
+        matches!(name, "btn" | "icon" | "indexterm" | "indexterm2" | "kbd" | "link" | "mailto" | "menu" | "pass" | "set" |
+            "xref")
+}
+
+/// Check whether the style of a paragraph makes it a verbatim block, like `[literal]`.
+fn is_verbatim_style(attributes: &[Attribute]) -> bool {
+    matches!(style(attributes), Some("listing") | Some("literal") | Some("source"))
+}
+
+/// Get the substitutions of a paragraph whose `subs` attribute only lists the special characters
+/// and attributes substitutions or is `none`, which disables all of them.
+fn paragraph_subs(attributes: &[Attribute]) -> Option<Vec<String>> {
+    let subs: Vec<_> = named(attributes, "subs")?.split(',').map(|sub| sub.trim().to_string()).collect();
+    let limited = subs.iter().all(|sub| matches!(sub.as_str(), "a" | "attributes" | "c" | "none" | "specialchars"));
+    if !limited {
+        return None;
+    }
+    Some(subs.into_iter().filter(|sub| sub != "none").collect())
+}
+
+/// Check whether a token can start a paragraph, rather than another kind of block.
+fn is_paragraph_start(token: &Token) -> bool {
+    match *token {
+        Word(ref word) =>
+            !is_section_marker(word) && word != b"=" && word != b"++++" && word != b"----" && !is_table_delimiter(word),
+        Backquote | Caret | CloseSquareBracket | ConstrainedPassthrough(_) | DoubleBackquote | DoubleStar |
+            DoubleUnderscore | NumberSign | Passthrough(_) | RawPassthrough(_) | Star | Tilde | Underscore | Xref(_) =>
+            true,
+        Colon | NewLine | OpenSquareBracket | Space | Token::Error(_) | TripleApos | TripleLt => false,
+    }
+}
+
+/// Check whether a word is the marker of a section title, from `==` to `======`.
+fn is_section_marker(word: &[u8]) -> bool {
+    word.len() >= 2 && word.len() <= 6 && word.iter().all(|&byte| byte == b'=')
+}
+
+/// Check whether a word delimits a table: `|===`, or `!===` for a nested table.
+fn is_table_delimiter(word: &[u8]) -> bool {
+    word == b"|===" || word == b"!==="
+}
+
+/// Get the position of the character at `offset` in `text`, which starts at the position `start` of
+/// the document.
+fn offset_pos(text: &str, offset: usize, start: Pos) -> Pos {
+    let before = &text[..offset];
+    match before.rfind('\n') {
+        Some(newline) => Pos::new(start.line + before.matches('\n').count(), offset - newline),
+        None => Pos::new(start.line, start.column + offset),
+    }
+}
+
+/// Get the value of the named attribute `name`.
+fn named<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Named(ref attribute_name, ref value) if attribute_name == name => Some(value.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
+/// Parse the content of a block anchor, like `id,reftext` in `[[id,reftext]]`: the reference text
+/// is kept in the `reftext` attribute.
+fn anchor_attributes(content: &str) -> Vec<Attribute> {
+    let mut parts = content.splitn(2, ',');
+    let mut attributes = vec![Id(parts.next().unwrap_or("").trim().to_string())];
+    if let Some(reftext) = parts.next() {
+        attributes.push(Named("reftext".to_string(), reftext.trim().to_string()));
+    }
+    attributes
+}
+
+/// Parse the content of an attribute list, like `#id`, `stem` or `source,rust,subs="+quotes"`.
+fn attribute_list(content: &str) -> Vec<Attribute> {
+    let mut attributes = vec![];
+    for (index, entry) in split_attribute_list(content).into_iter().enumerate() {
+        if let Some(equal) = entry.find('=') {
+            let name = entry[..equal].trim();
+            let value = unquote(entry[equal + 1..].trim()).to_string();
+            let attribute =
+                match name {
+                    "id" => Id(value),
+                    "role" => Role(value),
+                    _ => Named(name.to_string(), value),
+                };
+            attributes.push(attribute);
+        }
+        else if index == 0 {
+            shorthand_attributes(&entry, &mut attributes);
         }
         else {
-            return Err(Error::Msg("Should have got word token".to_string())); // TODO: better error.
+            attributes.push(Positional(unquote(&entry).to_string()));
+        }
+    }
+    attributes
+}
+
+/// Parse the attribute list of a block macro whose positional attributes are named, like `alt`,
+/// `width` and `height` for an image macro.
+fn macro_attributes(content: &str, positional_names: &[&str]) -> Vec<Attribute> {
+    let mut attributes = vec![];
+    for (index, entry) in split_attribute_list(content).into_iter().enumerate() {
+        if let Some(equal) = entry.find('=') {
+            let value = unquote(entry[equal + 1..].trim()).to_string();
+            attributes.push(Named(entry[..equal].trim().to_string(), value));
+        }
+        else if let Some(name) = positional_names.get(index) {
+            let value = unquote(entry.trim());
+            if !value.is_empty() {
+                attributes.push(Named(name.to_string(), value.to_string()));
+            }
+        }
+    }
+    attributes
+}
+
+/// Encode the characters of a query `value` which are not unreserved in URLs, like spaces as `%20`.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for &byte in value.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Split the path of a menu macro into its submenus and its item, like `Zoom > Reset`.
+/// The items are separated by `>`, or by commas when there is no `>`.
+fn menu_items(content: &str) -> Vec<String> {
+    if content.is_empty() {
+        return vec![];
+    }
+    let separator = if content.contains('>') { '>' } else { ',' };
+    content.split(separator).map(|item| item.trim().to_string()).collect()
+}
+
+/// Parse the shorthand syntax of the first positional attribute: a style followed by `#id`, `.role`
+/// and `%option` parts, like `quote#id.role1.role2` or `%autowidth`.
+/// The options are kept in the `options` attribute.
+fn shorthand_attributes(entry: &str, attributes: &mut Vec<Attribute>) {
+    let mut parts = vec![];
+    let mut start = 0;
+    for (index, char) in entry.char_indices() {
+        if index > 0 && (char == '#' || char == '.' || char == '%') {
+            parts.push(&entry[start..index]);
+            start = index;
+        }
+    }
+    parts.push(&entry[start..]);
+    for part in parts.into_iter().map(str::trim) {
+        if let Some(id) = part.strip_prefix('#') {
+            attributes.push(Id(id.to_string()));
+        }
+        else if let Some(role) = part.strip_prefix('.') {
+            attributes.push(Role(role.to_string()));
+        }
+        else if let Some(option) = part.strip_prefix('%') {
+            attributes.push(Named("options".to_string(), option.to_string()));
+        }
+        else if !part.is_empty() {
+            attributes.push(Style(part.to_string()));
+        }
+    }
+}
+
+/// Split the entries of an attribute list on the commas which are not between quotes.
+fn split_attribute_list(content: &str) -> Vec<String> {
+    let mut entries = vec![];
+    let mut entry = String::new();
+    let mut quote = None;
+    for char in content.chars() {
+        match (char, quote) {
+            (',', None) => {
+                entries.push(entry.trim().to_string());
+                entry.clear();
+                continue;
+            },
+            ('"', None) | ('\'', None) => quote = Some(char),
+            (_, Some(current)) if current == char => quote = None,
+            _ => (),
+        }
+        entry.push(char);
+    }
+    entries.push(entry.trim().to_string());
+    entries
+}
+
+/// Get the style of a block, which is its first positional attribute.
+fn style(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter()
+        .filter_map(|attribute|
+            match *attribute {
+                Style(ref style) => Some(style.as_str()),
+                _ => None,
+            })
+        .next()
+}
+
+/// Remove the quotes around an attribute value.
+fn unquote(value: &str) -> &str {
+    for quote in &['"', '\''] {
+        if value.len() >= 2 && value.starts_with(*quote) && value.ends_with(*quote) {
+            return &value[1..value.len() - 1];
         }
     }
+    value
+}
+
+/// Split the keys of a keyboard macro, like `Ctrl+Shift+N` or `Ctrl++`.
+fn keys(content: &str) -> Vec<String> {
+    if content == "+" {
+        return vec![content.to_string()];
+    }
+    if let Some(content) = content.strip_suffix("++") {
+        let mut keys = keys(content);
+        keys.push("+".to_string());
+        return keys;
+    }
+    content.split('+')
+        .map(|key| key.trim().to_string())
+        .collect()
+}
+
+/// Split the target of a cross reference into the path of its document, when it is another one,
+/// and its ID, like `other.adoc` and `id` for `other.adoc#id`.
+fn xref_target(target: &str) -> (Option<String>, String) {
+    match target.find('#') {
+        Some(index) if index > 0 => (Some(target[..index].to_string()), target[index + 1..].to_string()),
+        Some(_) => (None, target[1..].to_string()),
+        None if target.ends_with(".adoc") => (Some(target.to_string()), String::new()),
+        None => (None, target.to_string()),
+    }
 }
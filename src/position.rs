@@ -28,6 +28,16 @@ pub struct Pos {
     pub line: usize,
 }
 
+/// The range of a document covered by a node, from the position of its first character to the
+/// position of its last one, not counting its last newline.
+/// The parser gives the span of the top-level nodes, with `Parser::node_with_span()`, and of the
+/// blocks nested in them, in their `Block`.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub end: Pos,
+    pub start: Pos,
+}
+
 impl Pos {
     /// Create a new position from a line and a column.
     pub fn new(line: usize, column: usize) -> Self {
@@ -0,0 +1,55 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! The safe modes, which restrict what a document can access, to convert untrusted documents.
+
+/// The level of safety of a conversion, from the least to the most restrictive, like the safe
+/// modes of asciidoctor.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum SafeMode {
+    /// No restriction.
+    #[default]
+    Unsafe,
+    /// The included files must be in the base directory.
+    Safe,
+    /// Like `Safe`, for the documents converted by a server.
+    Server,
+    /// The include directives are replaced by links to their target and the raw passthroughs,
+    /// like `+++<b>raw</b>+++`, are escaped.
+    Secure,
+}
+
+impl SafeMode {
+    /// Check whether the include directives are expanded.
+    pub fn allows_includes(self) -> bool {
+        self < SafeMode::Secure
+    }
+
+    /// Check whether the included files can be outside the base directory.
+    pub fn allows_outside_files(self) -> bool {
+        self == SafeMode::Unsafe
+    }
+
+    /// Check whether the raw passthroughs are inserted as is in the output, rather than escaped.
+    pub fn allows_raw_passthroughs(self) -> bool {
+        self < SafeMode::Secure
+    }
+}
@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Parse the parts of a table: its column specifications and its cells.
+
+use node::{CellStyle, ColumnSpec, HAlign, VAlign};
+
+/// The source of a table cell, with the specification preceding its separator.
+pub struct RawCell {
+    pub content: String,
+    pub spec: CellSpec,
+    /// The offset of the content in the body of the table, to find its position in the document.
+    pub start: usize,
+}
+
+/// The specification of a table cell, like `2+` in `2+|cell` or `^m` in `^m|cell`.
+/// The alignments and style are `None` when they are taken from the column.
+#[derive(Clone)]
+pub struct CellSpec {
+    pub colspan: usize,
+    pub halign: Option<HAlign>,
+    /// The number of times the cell is duplicated, like `3` in `3*|cell`.
+    pub repeat: usize,
+    pub rowspan: usize,
+    pub style: Option<CellStyle>,
+    pub valign: Option<VAlign>,
+}
+
+impl Default for CellSpec {
+    fn default() -> Self {
+        CellSpec {
+            colspan: 1,
+            halign: None,
+            repeat: 1,
+            rowspan: 1,
+            style: None,
+            valign: None,
+        }
+    }
+}
+
+/// Parse a cell specification: `[repeat*|colspan.rowspan+][halign][.valign][style]`, like `2.3+^s`.
+/// Return `None` if `spec` is not a specification.
+fn cell_spec(spec: &str) -> Option<CellSpec> {
+    if spec.is_empty() {
+        return None;
+    }
+    let mut cell = CellSpec::default();
+    let factor_end = spec.find(['*', '+']).unwrap_or(0);
+    let rest =
+        if factor_end == 0 {
+            spec
+        } else {
+            let factor = &spec[..factor_end];
+            if spec[factor_end..].starts_with('*') {
+                cell.repeat = factor.parse().ok()?;
+            } else {
+                let (colspan, rowspan) =
+                    match factor.find('.') {
+                        Some(dot) => (&factor[..dot], &factor[dot + 1..]),
+                        None => (factor, ""),
+                    };
+                if !colspan.is_empty() {
+                    cell.colspan = colspan.parse().ok()?;
+                }
+                if !rowspan.is_empty() {
+                    cell.rowspan = rowspan.parse().ok()?;
+                }
+            }
+            &spec[factor_end + 1..]
+        };
+    let mut chars = rest.chars().peekable();
+    if let Some(halign) = chars.peek().cloned().and_then(halign) {
+        cell.halign = Some(halign);
+        chars.next();
+    }
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        cell.valign = Some(valign(chars.next()?)?);
+    }
+    if let Some(letter) = chars.next() {
+        cell.style = Some(CellStyle::from_letter(letter)?);
+    }
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(cell)
+}
+
+/// Split the body of a table into cells at each `separator`, `|` or `!` for a nested table.
+/// The specification of a cell is the last word directly before its separator, so `|a |b` has no
+/// specification, and its content spans up to the next separator, which may be on a following line.
+pub fn cells(body: &str, separator: char) -> Vec<RawCell> {
+    let mut parts = body.split(separator).peekable();
+    let mut cells = vec![];
+    let (mut spec, mut offset) =
+        match parts.next() {
+            Some(first) => (cell_spec(first.trim()).unwrap_or_default(), first.len() + separator.len_utf8()),
+            None => return cells,
+        };
+    while let Some(part) = parts.next() {
+        let start = offset + part.len() - part.trim_start().len();
+        offset += part.len() + separator.len_utf8();
+        let mut content = part.trim();
+        let mut next_spec = CellSpec::default();
+        if parts.peek().is_some() && !part.ends_with(char::is_whitespace) {
+            let spec_start = content.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+            if let Some(cell) = cell_spec(&content[spec_start..]) {
+                next_spec = cell;
+                content = content[..spec_start].trim_end();
+            }
+        }
+        for _ in 0..spec.repeat {
+            cells.push(RawCell {
+                content: content.to_string(),
+                spec: spec.clone(),
+                start,
+            });
+        }
+        spec = next_spec;
+    }
+    cells
+}
+
+/// The maximum number of columns of the `cols` attribute of a table, so that a repeat like
+/// `99999999*` does not allocate without bound.
+const MAX_COLUMNS: usize = 1000;
+
+/// Get the number of columns of a table without a `cols` attribute: the number of columns
+/// spanned by the cells of its first line, so a table with a single cell per line has one column.
+pub fn column_count(body: &str, separator: char) -> usize {
+    body.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map_or(0, |line| cells(line, separator).iter().map(|cell| cell.spec.colspan).sum())
+}
+
+/// Parse the `cols` attribute of a table, like `1,2*,>` or `3`, or get None when it has more than
+/// `MAX_COLUMNS` columns.
+pub fn column_specs(cols: &str) -> Option<Vec<ColumnSpec>> {
+    let cols = cols.trim();
+    if let Ok(count) = cols.parse::<usize>() {
+        return if count <= MAX_COLUMNS { Some(vec![ColumnSpec::default(); count]) } else { None };
+    }
+    let mut specs = vec![];
+    for spec in cols.split(&[',', ';'][..]) {
+        let (count, spec) = repeat(spec.trim());
+        if count > MAX_COLUMNS - specs.len() {
+            return None;
+        }
+        let spec = column_spec(spec);
+        for _ in 0..count {
+            specs.push(spec.clone());
+        }
+    }
+    Some(specs)
+}
+
+/// Parse a single column specification: `[halign][.valign][width][style]`, like `^.>2m`.
+fn column_spec(spec: &str) -> ColumnSpec {
+    let mut column = ColumnSpec::default();
+    let mut chars = spec.chars().peekable();
+    if let Some(halign) = chars.peek().cloned().and_then(halign) {
+        column.halign = halign;
+        chars.next();
+    }
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        if let Some(valign) = chars.peek().cloned().and_then(valign) {
+            column.valign = valign;
+            chars.next();
+        }
+    }
+    let mut width = String::new();
+    while let Some(&digit) = chars.peek() {
+        if !digit.is_ascii_digit() {
+            break;
+        }
+        width.push(digit);
+        chars.next();
+    }
+    if let Ok(width) = width.parse() {
+        column.width = width;
+    }
+    if chars.peek() == Some(&'%') {
+        chars.next();
+    }
+    if let Some(style) = chars.next().and_then(CellStyle::from_letter) {
+        column.style = style;
+    }
+    column
+}
+
+/// Parse the horizontal alignment operator of a specification.
+pub fn halign(operator: char) -> Option<HAlign> {
+    match operator {
+        '<' => Some(HAlign::Left),
+        '^' => Some(HAlign::Center),
+        '>' => Some(HAlign::Right),
+        _ => None,
+    }
+}
+
+/// Split the repeat count of a specification, like `2*` in `2*>`.
+fn repeat(spec: &str) -> (usize, &str) {
+    if let Some(star) = spec.find('*') {
+        if let Ok(count) = spec[..star].parse() {
+            return (count, &spec[star + 1..]);
+        }
+    }
+    (1, spec)
+}
+
+/// Group the cells into rows of `column_count` columns, taking the cells spanning several columns
+/// or rows into account.
+/// Each cell is returned with the index of its first column.
+pub fn rows(cells: Vec<RawCell>, column_count: usize) -> Vec<Vec<(usize, RawCell)>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    // The number of rows, including the current one, for which each column is taken.
+    let mut taken = vec![0; column_count];
+    for cell in cells {
+        let index =
+            match taken.iter().position(|&rows| rows == 0) {
+                Some(index) => index,
+                None => break,
+            };
+        for rows in taken.iter_mut().skip(index).take(cell.spec.colspan) {
+            *rows = cell.spec.rowspan;
+        }
+        row.push((index, cell));
+        while !taken.is_empty() && taken.iter().all(|&rows| rows > 0) {
+            rows.push(row);
+            row = vec![];
+            for rows in &mut taken {
+                *rows -= 1;
+            }
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Parse the vertical alignment operator of a specification, which follows a dot.
+pub fn valign(operator: char) -> Option<VAlign> {
+    match operator {
+        '<' => Some(VAlign::Top),
+        '^' => Some(VAlign::Middle),
+        '>' => Some(VAlign::Bottom),
+        _ => None,
+    }
+}
@@ -21,6 +21,10 @@
 
 //! Tokens from an asciidoctor document.
 
+use std::fmt::{self, Display, Formatter};
+use std::string::String;
+use std::vec::Vec;
+
 use self::Token::*;
 
 /// Different types of token.
@@ -29,12 +33,21 @@ pub enum Token {
     Backquote,
     Caret,
     CloseSquareBracket,
+    Colon,
+    /// A constrained inline passthrough, with the content between `+` and `+`.
+    ConstrainedPassthrough(Vec<u8>),
     DoubleBackquote,
     DoubleStar,
     DoubleUnderscore,
+    /// The first byte of a token which cannot be lexed, returned by the `RecoveringLexer`.
+    Error(u8),
     NewLine,
     NumberSign,
     OpenSquareBracket,
+    /// An inline passthrough, with the content between `++` and `++`.
+    Passthrough(Vec<u8>),
+    /// A raw inline passthrough, with the content between `+++` and `+++`, which is not escaped.
+    RawPassthrough(Vec<u8>),
     Space,
     Star,
     Tilde,
@@ -42,29 +55,49 @@ pub enum Token {
     TripleLt,
     Underscore,
     Word(Vec<u8>),
+    /// A cross reference, with the content between `<<` and `>>`.
+    Xref(Vec<u8>),
 }
 
 impl Token {
-    /// Convert the token to a user-readable string.
-    /// Useful for error reporting.
-    pub fn to_string(&self) -> String {
+    /// Get the text of the document which the token was lexed from, so that the source of a
+    /// document can be reassembled from its tokens, without its comments.
+    pub fn to_source(&self) -> String {
+        match *self {
+            NewLine => "\n".into(),
+            Space => " ".into(),
+            ref token => format!("{}", token),
+        }
+    }
+}
+
+/// Convert the token to a user-readable string.
+/// Useful for error reporting.
+impl Display for Token {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         match *self {
-            Backquote => "`".to_string(),
-            Caret => "^".to_string(),
-            CloseSquareBracket => "]".to_string(),
-            DoubleBackquote => "``".to_string(),
-            DoubleStar => "**".to_string(),
-            DoubleUnderscore => "__".to_string(),
-            NewLine => "(newline)".to_string(),
-            NumberSign => "#".to_string(),
-            OpenSquareBracket => "[".to_string(),
-            Space => "(space)".to_string(),
-            Star => "*".to_string(),
-            Tilde => "~".to_string(),
-            TripleApos => "'''".to_string(),
-            TripleLt => "<<<".to_string(),
-            Underscore => "_".to_string(),
-            Word(ref word) => String::from_utf8_lossy(word).to_string(),
+            Backquote => write!(fmt, "`"),
+            Caret => write!(fmt, "^"),
+            CloseSquareBracket => write!(fmt, "]"),
+            Colon => write!(fmt, ":"),
+            ConstrainedPassthrough(ref content) => write!(fmt, "+{}+", String::from_utf8_lossy(content)),
+            DoubleBackquote => write!(fmt, "``"),
+            DoubleStar => write!(fmt, "**"),
+            DoubleUnderscore => write!(fmt, "__"),
+            Error(byte) => write!(fmt, "{}", byte as char),
+            NewLine => write!(fmt, "(newline)"),
+            NumberSign => write!(fmt, "#"),
+            OpenSquareBracket => write!(fmt, "["),
+            Passthrough(ref content) => write!(fmt, "++{}++", String::from_utf8_lossy(content)),
+            RawPassthrough(ref content) => write!(fmt, "+++{}+++", String::from_utf8_lossy(content)),
+            Space => write!(fmt, "(space)"),
+            Star => write!(fmt, "*"),
+            Tilde => write!(fmt, "~"),
+            TripleApos => write!(fmt, "'''"),
+            TripleLt => write!(fmt, "<<<"),
+            Underscore => write!(fmt, "_"),
+            Word(ref word) => write!(fmt, "{}", String::from_utf8_lossy(word)),
+            Xref(ref content) => write!(fmt, "<<{}>>", String::from_utf8_lossy(content)),
         }
     }
 }
@@ -0,0 +1,78 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Traverse the nodes of a document without matching on all the kinds of nodes.
+
+use node::{Block, CellContent, Node};
+use node::Node::*;
+
+/// A visitor over the nodes of a document.
+/// By default, visiting a node visits the blocks nested in it, so that an implementation only
+/// needs to handle the nodes it is interested in and to call `walk_node` to keep descending.
+pub trait Visitor {
+    /// Visit the `node` and, by default, the blocks nested in it.
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    /// Visit the nodes of the `blocks` nested in a node.
+    fn visit_blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.visit_node(&block.node);
+        }
+    }
+
+    /// Visit all the `nodes` of a document.
+    fn visit_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit_node(node);
+        }
+    }
+}
+
+/// Visit the blocks nested in the `node`: those of the sections, list items and AsciiDoc table
+/// cells.
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    match *node {
+        Admonition(_, ref blocks, _) | Example(ref blocks, _) => visitor.visit_blocks(blocks),
+        DescriptionList(ref items, _) => {
+            for item in items {
+                visitor.visit_blocks(&item.blocks);
+            }
+        },
+        Preamble(ref blocks) => visitor.visit_blocks(blocks),
+        Section(ref section) => visitor.visit_blocks(&section.blocks),
+        Table(ref table) => {
+            for cell in table.rows.iter().flat_map(|row| row.iter()) {
+                if let CellContent::Blocks(ref blocks) = cell.content {
+                    visitor.visit_blocks(blocks);
+                }
+            }
+        },
+        UnorderedList(ref items, _) => {
+            for item in items {
+                visitor.visit_blocks(&item.blocks);
+            }
+        },
+        AttributeEntry(..) | Header(_) | HorizontalRule | Image { .. } | Listing(..) | Literal(..) | Media { .. } |
+            PageBreak | Paragraph(..) | PassthroughBlock { .. } | Raw(..) | Stem { .. } | Toc(_) => (),
+    }
+}
@@ -0,0 +1,214 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! The lexer only reads from byte slices here, so these tests also run without the `std` feature:
+//! `cargo test --no-default-features --test slice_lexer`.
+
+extern crate asciidoctor;
+
+use asciidoctor::{Error, Lexer, Pos, RecoveringLexer, Token};
+
+#[test]
+fn test_slice_lexer() {
+    let mut lexer = Lexer::new(&b"*bold* <<id>>\n"[..]);
+    let mut tokens = vec![];
+    loop {
+        match lexer.token() {
+            Ok(token) => tokens.push(token),
+            Err(Error::Eof) => break,
+            Err(error) => panic!("cannot tokenize: {}", error),
+        }
+    }
+    assert_eq!(tokens, vec![
+        Token::Star,
+        Token::Word(b"bold".to_vec()),
+        Token::Star,
+        Token::Space,
+        Token::Xref(b"id".to_vec()),
+        Token::NewLine,
+    ]);
+}
+
+#[test]
+fn test_from_bytes() {
+    let line = &b"= Title\n:attr: value\n\n* item with *bold* and _italic_ `code` ^sup^ ~sub~\n[#id.role]\n<<xref,text>> ++pass++ // comment\n\n"[..];
+    let mut input = vec![];
+    while input.len() < 10000 {
+        input.extend_from_slice(line);
+    }
+    for input in &[line, &input[..]] {
+        assert_eq!(tokens(Lexer::from_bytes(input)), tokens(Lexer::new(*input)));
+    }
+}
+
+#[test]
+fn test_into_inner() {
+    let mut input = b"First line\n".to_vec();
+    while input.len() < 10000 {
+        input.extend_from_slice(b"*bold* and `code`\n");
+    }
+    input.extend_from_slice(b"last");
+    let mut lexer = Lexer::new(&input[..]);
+    assert_eq!(lexer.line().unwrap(), b"First line");
+    let (reader, mut remaining) = lexer.into_inner();
+    assert!(!remaining.is_empty() && !reader.is_empty());
+    remaining.extend_from_slice(reader);
+    assert_eq!(remaining, &input[11..]);
+    assert_eq!(tokens(Lexer::new(&remaining[..])), tokens(Lexer::new(&input[11..])));
+
+    let mut lexer = Lexer::new(&b"one\ntwo three\n"[..]);
+    lexer.line().unwrap();
+    assert_eq!((lexer.pos().line, lexer.pos().column), (2, 1));
+    lexer.reset_position(Pos::new(1, 1));
+    assert_eq!(lexer.token().unwrap(), Token::Word(b"two".to_vec()));
+    assert_eq!((lexer.pos().line, lexer.pos().column), (1, 4));
+}
+
+#[test]
+fn test_last_line_without_newline() {
+    let expected = vec![Token::Word(b"hello".to_vec()), Token::Space, Token::Word(b"world".to_vec()), Token::NewLine];
+    assert_eq!(tokens(Lexer::new(&b"hello world"[..])), expected);
+    assert_eq!(tokens(Lexer::from_bytes(b"hello world")), expected);
+    assert_eq!(tokens(Lexer::new(&b"hello world\n"[..])), expected);
+    assert!(tokens(Lexer::new(&b""[..])).is_empty());
+}
+
+#[test]
+fn test_passthroughs() {
+    assert_eq!(tokens(Lexer::new(&b"++a+b++ +++<b>+++ ++++ +++open\n"[..])), vec![
+        Token::Passthrough(b"a+b".to_vec()),
+        Token::Space,
+        Token::RawPassthrough(b"<b>".to_vec()),
+        Token::Space,
+        Token::Word(b"++++".to_vec()),
+        Token::Space,
+        Token::Word(b"+++open".to_vec()),
+        Token::NewLine,
+    ]);
+    assert_eq!(tokens(Lexer::new(&b"+*b*+ + +x+y +1\n"[..])), vec![
+        Token::ConstrainedPassthrough(b"*b*".to_vec()),
+        Token::Space,
+        Token::Word(b"+".to_vec()),
+        Token::Space,
+        Token::Word(b"+x+y".to_vec()),
+        Token::Space,
+        Token::Word(b"+1".to_vec()),
+        Token::NewLine,
+    ]);
+
+    assert_eq!(tokens(Lexer::new(&b"a ++ *b*\n"[..])), vec![
+        Token::Word(b"a".to_vec()),
+        Token::Space,
+        Token::Word(b"++".to_vec()),
+        Token::Space,
+        Token::Star,
+        Token::Word(b"b".to_vec()),
+        Token::Star,
+        Token::NewLine,
+    ]);
+
+    let mut lexer = Lexer::new(&b"a +x\xff+\n"[..]);
+    lexer.token().unwrap();
+    lexer.token().unwrap();
+    match lexer.token() {
+        Err(Error::InvalidUtf8(pos)) => assert_eq!((pos.line, pos.column), (1, 5)),
+        result => panic!("expected invalid UTF-8, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_to_source() {
+    let input = "= Title\n:attr: value\n\n* item with *bold*, __italic__ and ``code`` ^sup^ ~sub~\n[#id.role]\n\
+        <<xref,text>> ++pass++ +++<b>raw</b>+++ #mark#\n'''\n<<<\n// comment\nLast line\n";
+    let source: String = tokens(Lexer::new(input.as_bytes())).iter().map(Token::to_source).collect();
+    assert_eq!(source, input.replace("// comment", ""));
+
+    for input in &["+x+ y\n", "++x++ y\n", "a +*b*+ ++c++ +++d+++\n"] {
+        let source: String = tokens(Lexer::new(input.as_bytes())).iter().map(Token::to_source).collect();
+        assert_eq!(source.as_bytes(), input.as_bytes());
+    }
+}
+
+#[test]
+fn test_comments() {
+    assert_eq!(tokens(Lexer::new(&b"see http://x.com here\n"[..])), vec![
+        Token::Word(b"see".to_vec()),
+        Token::Space,
+        Token::Word(b"http".to_vec()),
+        Token::Colon,
+        Token::Word(b"//x.com".to_vec()),
+        Token::Space,
+        Token::Word(b"here".to_vec()),
+        Token::NewLine,
+    ]);
+    assert_eq!(tokens(Lexer::new(&b"// note\ntext /path a //b\n  // indented\n"[..])), vec![
+        Token::NewLine,
+        Token::Word(b"text".to_vec()),
+        Token::Space,
+        Token::Word(b"/path".to_vec()),
+        Token::Space,
+        Token::Word(b"a".to_vec()),
+        Token::Space,
+        Token::Word(b"//b".to_vec()),
+        Token::NewLine,
+        Token::Space,
+        Token::Space,
+        Token::NewLine,
+    ]);
+}
+
+#[test]
+fn test_recovering_lexer() {
+    let tokens: Vec<_> = RecoveringLexer::new(Lexer::new(&b"a 'b ''c\n"[..]))
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(tokens, vec![
+        Token::Word(b"a".to_vec()),
+        Token::Space,
+        Token::Error(b'\''),
+        Token::Word(b"b".to_vec()),
+        Token::Space,
+        Token::Error(b'\''),
+        Token::Error(b'\''),
+        Token::Word(b"c".to_vec()),
+        Token::NewLine,
+    ]);
+
+    let tokens: Vec<_> = RecoveringLexer::new(Lexer::new(&b"''x\n"[..])).map(Result::unwrap).collect();
+    assert_eq!(tokens, vec![Token::Error(b'\''), Token::Error(b'\''), Token::Word(b"x".to_vec()), Token::NewLine]);
+
+    let tokens: Vec<_> = RecoveringLexer::new(Lexer::new(&b"a\tb"[..])).collect();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(*tokens[0].as_ref().unwrap(), Token::Word(b"a".to_vec()));
+    assert!(tokens[1].is_err());
+}
+
+fn tokens(mut lexer: Lexer<&[u8]>) -> Vec<Token> {
+    let mut tokens = vec![];
+    loop {
+        match lexer.token() {
+            Ok(token) => tokens.push(token),
+            Err(Error::Eof) => break,
+            Err(error) => panic!("cannot tokenize: {}", error),
+        }
+    }
+    tokens
+}